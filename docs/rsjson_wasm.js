@@ -1,5 +1,26 @@
 /* @ts-self-types="./rsjson_wasm.d.ts" */
 
+/**
+ * Every problem in `input` as a JSON array of `{code, message, span}`
+ * objects, empty when the input is valid.
+ * @param {string} input
+ * @returns {string}
+ */
+export function diagnostics(input) {
+    let deferred2_0;
+    let deferred2_1;
+    try {
+        const ptr0 = passStringToWasm0(input, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.diagnostics(ptr0, len0);
+        deferred2_0 = ret[0];
+        deferred2_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred2_0, deferred2_1, 1);
+    }
+}
+
 /**
  * @param {string} input
  * @returns {string}
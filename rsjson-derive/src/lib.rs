@@ -0,0 +1,232 @@
+//! Derive macros for the `FromJson` and `ToJson` traits of `rsjson`, enabled
+//! through its `derive` feature.
+//!
+//! Both derives support structs with named fields. Fields take `#[json(...)]`
+//! attributes:
+//!
+//! - `rename = "name"` reads and writes the field under another key.
+//! - `default` uses `Default::default()` when the key is missing.
+//! - `skip` never writes the field and always reads it as its default.
+//! - `flatten` reads and writes the field's own members as members of the
+//!   enclosing object, rather than as a nested object.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Generics, Ident, LitStr, Type,
+};
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_json(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_json(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+// A struct field and its `#[json(...)]` attributes.
+struct Field<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    name: String,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+impl<'a> Field<'a> {
+    fn parse(field: &'a syn::Field) -> syn::Result<Field<'a>> {
+        let ident = field.ident.as_ref().expect("named fields have names");
+        let mut parsed = Field {
+            ident,
+            ty: &field.ty,
+            name: ident.to_string().trim_start_matches("r#").to_string(),
+            default: false,
+            skip: false,
+            flatten: false,
+        };
+
+        let mut renamed = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("json"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    parsed.name = meta.value()?.parse::<LitStr>()?.value();
+                    renamed = true;
+                } else if meta.path.is_ident("default") {
+                    parsed.default = true;
+                } else if meta.path.is_ident("skip") {
+                    parsed.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    parsed.flatten = true;
+                } else {
+                    return Err(meta.error(
+                        "unknown json attribute, expected `rename`, `default`, `skip` or `flatten`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+
+        if parsed.flatten && (renamed || parsed.skip) {
+            return Err(Error::new_spanned(
+                field,
+                "`flatten` cannot be combined with `rename` or `skip`",
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Vec<Field<'_>>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Some(&fields.named),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let Some(fields) = fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "FromJson and ToJson can only be derived for structs with named fields",
+        ));
+    };
+
+    fields.iter().map(Field::parse).collect()
+}
+
+// Requires every type parameter to implement `bound`.
+fn with_bound(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+fn to_json(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let members = fields(input)?
+        .into_iter()
+        .filter(|field| !field.skip)
+        .map(|field| {
+            let ident = field.ident;
+            let name = &field.name;
+            if field.flatten {
+                // Values other than objects, such as a `None`, add no members.
+                quote! {
+                    if let ::rsjson::JsonValue::Object(members) =
+                        ::rsjson::ToJson::to_json(&self.#ident)
+                    {
+                        object.extend(members);
+                    }
+                }
+            } else {
+                quote! {
+                    object.insert(
+                        ::std::string::String::from(#name),
+                        ::rsjson::ToJson::to_json(&self.#ident),
+                    );
+                }
+            }
+        });
+
+    let ident = &input.ident;
+    let generics = with_bound(&input.generics, quote!(::rsjson::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rsjson::ToJson for #ident #ty_generics #where_clause {
+            fn to_json(&self) -> ::rsjson::JsonValue {
+                #[allow(unused_mut)]
+                let mut object = ::rsjson::Map::new();
+                #(#members)*
+                ::rsjson::JsonValue::Object(object)
+            }
+        }
+    })
+}
+
+fn from_json(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = fields(input)?;
+
+    let inits = fields.iter().map(|field| {
+        let ident = field.ident;
+        let ty = field.ty;
+        let name = &field.name;
+
+        if field.skip {
+            return quote!(#ident: ::std::default::Default::default());
+        }
+        if field.flatten {
+            return quote!(#ident: <#ty as ::rsjson::FromJson>::from_json(&rest)?);
+        }
+
+        let missing = if field.default {
+            quote!(::std::default::Default::default())
+        } else {
+            quote! {
+                <#ty as ::rsjson::FromJson>::from_missing()
+                    .ok_or_else(|| ::rsjson::FromJsonError::missing_field(#name))?
+            }
+        };
+
+        quote! {
+            #ident: match object.get(#name) {
+                ::std::option::Option::Some(value) => {
+                    <#ty as ::rsjson::FromJson>::from_json(value).map_err(|err| err.at_key(#name))?
+                }
+                ::std::option::Option::None => #missing,
+            }
+        }
+    });
+
+    // Flattened fields read the members that no other field reads.
+    let rest = fields.iter().any(|field| field.flatten).then(|| {
+        let names = fields
+            .iter()
+            .filter(|field| !field.skip && !field.flatten)
+            .map(|field| &field.name);
+
+        quote! {
+            let rest: ::rsjson::JsonValue = object
+                .iter()
+                .filter(|(key, _)| ![#(#names),*].contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+        }
+    });
+
+    let ident = &input.ident;
+    let generics = with_bound(&input.generics, quote!(::rsjson::FromJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rsjson::FromJson for #ident #ty_generics #where_clause {
+            fn from_json(
+                value: &::rsjson::JsonValue,
+            ) -> ::std::result::Result<Self, ::rsjson::FromJsonError> {
+                let object = value.as_object().ok_or_else(|| {
+                    ::rsjson::FromJsonError::invalid_type(::rsjson::JsonKind::Object, value)
+                })?;
+                #rest
+
+                ::std::result::Result::Ok(Self { #(#inits),* })
+            }
+        }
+    })
+}
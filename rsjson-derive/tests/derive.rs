@@ -0,0 +1,135 @@
+use rsjson::{json, parse, FromJson, FromJsonError, FromJsonErrorKind, JsonKind, ToJson};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Service {
+    name: String,
+    #[json(rename = "port-number")]
+    port: u16,
+    #[json(default)]
+    replicas: u32,
+    tags: Vec<String>,
+    owner: Option<String>,
+    #[json(skip)]
+    cache: Vec<u8>,
+    limits: Limits,
+}
+
+#[derive(Debug, Default, PartialEq, FromJson, ToJson)]
+struct Limits {
+    cpu: f64,
+    memory: u64,
+}
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Labelled<T> {
+    id: T,
+    #[json(flatten)]
+    labels: HashMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Page {
+    #[json(flatten)]
+    limits: Limits,
+    r#type: String,
+}
+
+fn service() -> Service {
+    Service {
+        name: "api".to_string(),
+        port: 8080,
+        replicas: 0,
+        tags: vec!["web".to_string()],
+        owner: None,
+        cache: Vec::new(),
+        limits: Limits {
+            cpu: 0.5,
+            memory: 512,
+        },
+    }
+}
+
+#[test]
+fn round_trips_a_struct() {
+    let value = json!({
+        "name": "api",
+        "port-number": 8080,
+        "tags": ["web"],
+        "limits": { "cpu": 0.5, "memory": 512 }
+    });
+
+    let parsed = Service::from_json(&value).unwrap();
+    assert_eq!(parsed, service());
+
+    // Defaults and `None` are written back, skipped fields are not.
+    let expected = json!({
+        "name": "api",
+        "port-number": 8080,
+        "replicas": 0,
+        "tags": ["web"],
+        "owner": null,
+        "limits": { "cpu": 0.5, "memory": 512 }
+    });
+    assert_eq!(parsed.to_json(), expected);
+    assert_eq!(Service::from_json(&expected).unwrap(), service());
+}
+
+#[test]
+fn skipped_fields_are_not_read() {
+    let mut value = service().to_json();
+    value["cache"] = json!([1, 2, 3]);
+    assert_eq!(Service::from_json(&value).unwrap().cache, Vec::<u8>::new());
+}
+
+#[test]
+fn errors_carry_the_path_of_the_field() {
+    let mut value = service().to_json();
+    value["limits"]["memory"] = json!(-1);
+    let err = Service::from_json(&value).unwrap_err();
+    assert_eq!(err.pointer(), "/limits/memory");
+    assert_eq!(err.to_string(), "expected u64, got -1 at /limits/memory");
+
+    value["tags"][0] = json!(7);
+    let err = Service::from_json(&value).unwrap_err();
+    assert_eq!(err.to_string(), "expected string, got number at /tags/0");
+
+    value.remove("port-number");
+    let err = Service::from_json(&value).unwrap_err();
+    assert_eq!(err.kind(), &FromJsonErrorKind::MissingField);
+    assert_eq!(err.pointer(), "/port-number");
+
+    let err = Limits::from_json(&json!([1, 2])).unwrap_err();
+    assert_eq!(
+        err,
+        FromJsonError::invalid_type(JsonKind::Object, &json!([]))
+    );
+}
+
+#[test]
+fn flatten_reads_and_writes_the_remaining_members() {
+    let value = parse(r#"{"id": 7, "team": "core", "tier": "1"}"#).unwrap();
+    let labelled = Labelled::<u32>::from_json(&value).unwrap();
+    assert_eq!(labelled.id, 7);
+    assert_eq!(labelled.labels.len(), 2);
+    assert_eq!(labelled.labels["team"], "core");
+    assert_eq!(labelled.to_json(), value);
+
+    let page = Page::from_json(&json!({"cpu": 1.0, "memory": 2, "type": "small"})).unwrap();
+    assert_eq!(
+        page.limits,
+        Limits {
+            cpu: 1.0,
+            memory: 2
+        }
+    );
+    assert_eq!(page.r#type, "small");
+
+    let written = page.to_json();
+    assert_eq!(written["memory"].as_u64(), Some(2));
+    assert_eq!(written["type"].as_str(), Some("small"));
+
+    // A failing flattened field reports the path within the enclosing object.
+    let err = Page::from_json(&json!({"cpu": "x", "memory": 2, "type": "small"})).unwrap_err();
+    assert_eq!(err.pointer(), "/cpu");
+}
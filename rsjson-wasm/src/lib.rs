@@ -7,4 +7,13 @@ pub fn print(input : &str) -> String {
         Ok(value) => value.to_string(),
         Err(err) => format!("{}",err)
     }
-}
\ No newline at end of file
+}
+
+/// Every problem in `input` as a JSON array of `{code, message, span}`
+/// objects, empty when the input is valid.
+#[wasm_bindgen]
+pub fn diagnostics(input: &str) -> String {
+    let (_, diagnostics) = rsjson::parse_with_recovery(input);
+    let diagnostics = diagnostics.iter().map(|diagnostic| diagnostic.to_json()).collect();
+    rsjson::JsonValue::Array(diagnostics).to_string()
+}
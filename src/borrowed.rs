@@ -0,0 +1,194 @@
+use crate::{JsonValue, Map, Number};
+use std::borrow::Cow;
+
+/// A JSON value whose strings and keys borrow from the input it was parsed
+/// from, as returned by [`parse_borrowed`](crate::parse_borrowed).
+///
+/// Strings without escape sequences point into the input, and only those that
+/// need decoding own their text. [`into_owned`](JsonValueRef::into_owned)
+/// converts the value to a [`JsonValue`] that no longer depends on the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValueRef<'a> {
+    Object(Map<Cow<'a, str>, JsonValueRef<'a>>),
+    Array(Vec<JsonValueRef<'a>>),
+    String(Cow<'a, str>),
+    Number(Number),
+    Boolean(bool),
+    Null,
+}
+
+impl<'a> JsonValueRef<'a> {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValueRef::String(str) => Some(str),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValueRef::Number(num) => Some(num.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValueRef::Number(num) => num.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValueRef::Number(num) => num.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValueRef::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValueRef<'a>> {
+        match self {
+            JsonValueRef::Object(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&JsonValueRef<'a>> {
+        match self {
+            JsonValueRef::Array(arr) => arr.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValueRef::Null)
+    }
+
+    /// Drops the value without recursing, see [`JsonValue::drop_iteratively`].
+    pub fn drop_iteratively(self) {
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            match value {
+                JsonValueRef::Array(values) => stack.extend(values),
+                JsonValueRef::Object(map) => stack.extend(map.into_iter().map(|(_, value)| value)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Converts to a [`JsonValue`], copying the strings that are still
+    /// borrowed from the input.
+    pub fn into_owned(self) -> JsonValue {
+        match self {
+            JsonValueRef::Object(object) => JsonValue::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                    .collect(),
+            ),
+            JsonValueRef::Array(values) => {
+                JsonValue::Array(values.into_iter().map(JsonValueRef::into_owned).collect())
+            }
+            JsonValueRef::String(str) => JsonValue::String(str.into_owned()),
+            JsonValueRef::Number(num) => JsonValue::Number(num),
+            JsonValueRef::Boolean(value) => JsonValue::Boolean(value),
+            JsonValueRef::Null => JsonValue::Null,
+        }
+    }
+}
+
+impl From<JsonValueRef<'_>> for JsonValue {
+    fn from(value: JsonValueRef<'_>) -> JsonValue {
+        value.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_borrowed, parse_borrowed_with_options, DuplicateKeys, ParseOptions};
+
+    fn is_borrowed(value: Option<&JsonValueRef<'_>>) -> bool {
+        matches!(value, Some(JsonValueRef::String(Cow::Borrowed(_))))
+    }
+
+    #[test]
+    fn strings_without_escapes_are_borrowed() {
+        let input = r#"{"plain": "text", "escaped": "a\nb", "unicode": "é"}"#;
+        let value = parse_borrowed(input).unwrap();
+
+        assert!(is_borrowed(value.get("plain")));
+        assert!(!is_borrowed(value.get("escaped")));
+        assert_eq!(
+            value.get("escaped").and_then(JsonValueRef::as_str),
+            Some("a\nb")
+        );
+        assert_eq!(
+            value.get("unicode").and_then(JsonValueRef::as_str),
+            Some("é")
+        );
+
+        // The borrowed text is the slice of the input between the quotes.
+        let Some(JsonValueRef::String(Cow::Borrowed(text))) = value.get("plain") else {
+            unreachable!()
+        };
+        assert_eq!(text.as_ptr(), input[11..].as_ptr());
+    }
+
+    #[test]
+    fn keys_are_borrowed_unless_escaped() {
+        let value = parse_borrowed(r#"{"a": 1, "b\"c": 2}"#).unwrap();
+        let JsonValueRef::Object(object) = &value else {
+            panic!("expected an object, got {value:?}");
+        };
+
+        let keys: Vec<bool> = object
+            .keys()
+            .map(|key| matches!(key, Cow::Borrowed(_)))
+            .collect();
+        assert_eq!(keys, [true, false]);
+        assert_eq!(value.get("b\"c").and_then(JsonValueRef::as_i64), Some(2));
+    }
+
+    #[test]
+    fn into_owned_matches_parse() {
+        for input in [
+            r#"{"name": "rsjson", "tags": ["a\tb", "c"], "n": -1.5, "ok": true, "none": null}"#,
+            r#"[[], {}, "😀", 18446744073709551615]"#,
+            r#""top level""#,
+        ] {
+            let owned: JsonValue = parse_borrowed(input).unwrap().into();
+            assert_eq!(owned, parse(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn errors_and_options_match_parse() {
+        for input in [r#"{"a": "\x"}"#, "[1, 2", r#"{"a": 1, "a": 2}"#] {
+            let (borrowed, owned) = (parse_borrowed(input), parse(input));
+            assert_eq!(
+                borrowed.unwrap_err().to_string(),
+                owned.unwrap_err().to_string()
+            );
+        }
+
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::Collect,
+            ..ParseOptions::default()
+        };
+        let value = parse_borrowed_with_options(r#"{"a": 1, "a": "x", "a": 3}"#, &options).unwrap();
+        let values = match value.get("a") {
+            Some(JsonValueRef::Array(values)) => values,
+            other => panic!("expected collected values, got {other:?}"),
+        };
+        assert_eq!(values.len(), 3);
+        assert!(is_borrowed(values.get(1)));
+    }
+}
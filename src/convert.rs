@@ -0,0 +1,171 @@
+use crate::{JsonValue, Map, Number};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    hash::BuildHasher,
+};
+
+macro_rules! from_number {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for JsonValue {
+                fn from(n: $ty) -> JsonValue {
+                    JsonValue::Number(Number::from(n))
+                }
+            }
+        )*
+    };
+}
+
+from_number!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl From<Number> for JsonValue {
+    fn from(n: Number) -> JsonValue {
+        JsonValue::Number(n)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> JsonValue {
+        JsonValue::Boolean(value)
+    }
+}
+
+impl From<()> for JsonValue {
+    fn from((): ()) -> JsonValue {
+        JsonValue::Null
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(str: &str) -> JsonValue {
+        JsonValue::String(str.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(str: String) -> JsonValue {
+        JsonValue::String(str)
+    }
+}
+
+impl From<Cow<'_, str>> for JsonValue {
+    fn from(str: Cow<'_, str>) -> JsonValue {
+        JsonValue::String(str.into_owned())
+    }
+}
+
+/// `None` converts to `null`.
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> JsonValue {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(values: Vec<T>) -> JsonValue {
+        values.into_iter().collect()
+    }
+}
+
+impl<T: Into<JsonValue> + Clone> From<&[T]> for JsonValue {
+    fn from(values: &[T]) -> JsonValue {
+        values.iter().cloned().collect()
+    }
+}
+
+impl From<Map> for JsonValue {
+    fn from(map: Map) -> JsonValue {
+        JsonValue::Object(map)
+    }
+}
+
+/// The members keep the map's iteration order, which for a `HashMap` is
+/// arbitrary.
+impl<K: Into<String>, V: Into<JsonValue>, S: BuildHasher> From<HashMap<K, V, S>> for JsonValue {
+    fn from(map: HashMap<K, V, S>) -> JsonValue {
+        map.into_iter().collect()
+    }
+}
+
+impl<K: Into<String>, V: Into<JsonValue>> From<BTreeMap<K, V>> for JsonValue {
+    fn from(map: BTreeMap<K, V>) -> JsonValue {
+        map.into_iter().collect()
+    }
+}
+
+/// Collects values into an array.
+impl<T: Into<JsonValue>> FromIterator<T> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> JsonValue {
+        JsonValue::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Collects key-value pairs into an object. A repeated key keeps its first
+/// position and its last value.
+impl<K: Into<String>, V: Into<JsonValue>> FromIterator<(K, V)> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> JsonValue {
+        let members = iter
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()));
+        JsonValue::Object(members.collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn from_scalars() {
+        assert_eq!(JsonValue::from(true), JsonValue::Boolean(true));
+        assert_eq!(JsonValue::from(()), JsonValue::Null);
+        assert_eq!(JsonValue::from(7u8).as_u64(), Some(7));
+        assert_eq!(JsonValue::from(-7i16).as_i64(), Some(-7));
+        assert_eq!(JsonValue::from(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(JsonValue::from(-7i128).as_i64(), Some(-7));
+        assert_eq!(JsonValue::from(7u128).as_u64(), Some(7));
+        assert_eq!(
+            JsonValue::from(u128::MAX).to_string(),
+            "340282366920938463463374607431768211455"
+        );
+        assert_eq!(JsonValue::from(1.5f32).as_f64(), Some(1.5));
+        assert_eq!(JsonValue::from("a").as_str(), Some("a"));
+        assert_eq!(JsonValue::from(String::from("b")).as_str(), Some("b"));
+        assert_eq!(JsonValue::from(Cow::Borrowed("c")).as_str(), Some("c"));
+        assert_eq!(JsonValue::from(None::<i32>), JsonValue::Null);
+        assert_eq!(JsonValue::from(Some(2)).as_i64(), Some(2));
+    }
+
+    #[test]
+    fn from_collections() {
+        assert_eq!(JsonValue::from(vec![1, 2]), parse("[1, 2]").unwrap());
+        assert_eq!(
+            JsonValue::from(&["x", "y"][..]),
+            parse(r#"["x", "y"]"#).unwrap()
+        );
+        assert_eq!(
+            JsonValue::from(vec![Some(true), None]),
+            parse("[true, null]").unwrap()
+        );
+
+        let expected = parse(r#"{"a": 1, "b": [2]}"#).unwrap();
+        let btree = BTreeMap::from([("b", vec![2]), ("a", vec![])]);
+        let mut value = JsonValue::from(btree);
+        value["a"] = 1.into();
+        assert_eq!(value, expected);
+
+        let hash: HashMap<String, i32> = HashMap::from([("n".to_string(), 1)]);
+        assert_eq!(JsonValue::from(hash), parse(r#"{"n": 1}"#).unwrap());
+    }
+
+    #[test]
+    fn collect_arrays_and_objects() {
+        let array: JsonValue = (1..=3).collect();
+        assert_eq!(array, parse("[1, 2, 3]").unwrap());
+
+        let object: JsonValue = [("z", 1), ("a", 2), ("z", 3)].into_iter().collect();
+        assert_eq!(object.to_string(), "{\n    \"z\": 3,\n    \"a\": 2\n}");
+    }
+}
@@ -0,0 +1,817 @@
+use crate::{
+    lexer::{Lexer, Token, TokenKind},
+    parser::Parser,
+    DuplicateKeys, JsonError, JsonValue, Map, Number, ParseOptions,
+};
+use serde::de::{
+    self, Deserialize, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+use std::{borrow::Cow, collections::HashSet, fmt};
+
+/// Deserialises a `T` from JSON text.
+///
+/// The input is read token by token into `T` without building a [`JsonValue`]
+/// first. Strings without escapes are borrowed from `input` when `T` accepts
+/// a `&str`. Errors carry the position of the token being read, including
+/// those raised by `T` itself, such as a missing field or a value of the wrong
+/// type. A key repeated within an object is a [`JsonError::DuplicateKey`], as
+/// it is for [`parse`](crate::parse).
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// let point: (i32, i32) = rsjson::from_str("[1, -2]").unwrap();
+/// assert_eq!(point, (1, -2));
+///
+/// let err = rsjson::from_str::<(i32, i32)>("[1,\n \"2\"]").unwrap_err();
+/// assert_eq!(err.span().unwrap().line, 2);
+/// # }
+/// ```
+pub fn from_str<'a, T: Deserialize<'a>>(input: &'a str) -> Result<T, JsonError> {
+    let mut deserializer = Deserializer::new(input);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.parser.finish()?;
+    Ok(value)
+}
+
+impl de::Error for JsonError {
+    // The position is filled in by the deserializer once the error reaches it.
+    fn custom<T: fmt::Display>(message: T) -> JsonError {
+        JsonError::Custom {
+            message: message.to_string(),
+            span: None,
+        }
+    }
+}
+
+// Drives a `Deserialize` implementation directly from the tokens of the input.
+struct Deserializer<'de> {
+    parser: Parser<'de>,
+    // A token read ahead to decide how to deserialise a value, which that
+    // value then consumes.
+    peeked: Option<Token>,
+    depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    fn new(input: &'de str) -> Deserializer<'de> {
+        let options = ParseOptions::default();
+        let lexer = Lexer::with_column_unit(options.column_unit);
+        Deserializer {
+            parser: Parser::new(input.as_bytes(), lexer, &options),
+            peeked: None,
+            depth: 0,
+        }
+    }
+
+    fn peek(&mut self, expected: &'static str) -> Result<TokenKind, JsonError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(Parser::next_token(&mut self.parser, expected)?);
+        }
+        match &self.peeked {
+            Some(token) => Ok(token.kind.clone()),
+            None => unreachable!("a token was just peeked"),
+        }
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<Token, JsonError> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => Parser::next_token(&mut self.parser, expected),
+        }
+    }
+
+    // Gives errors raised by `Deserialize` implementations the position of
+    // the last token read.
+    fn locate(&self, err: JsonError) -> JsonError {
+        match err {
+            JsonError::Custom {
+                message,
+                span: None,
+            } => JsonError::Custom {
+                message,
+                span: Some(self.parser.lexer.last_token_span()),
+            },
+            err => err,
+        }
+    }
+
+    // Called on an opening bracket.
+    fn enter(&mut self) -> Result<(), JsonError> {
+        Parser::check_depth(&self.parser, self.depth)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    // Reads the bracket that closes the container just deserialised.
+    fn close(&mut self, bracket: TokenKind) -> Result<(), JsonError> {
+        self.depth -= 1;
+        let token = self.next(bracket.describe())?;
+        if token.kind != bracket {
+            return Err(Parser::unexpected(&token, &self.parser, bracket.describe()));
+        }
+
+        Ok(())
+    }
+
+    fn string(&mut self, token: &Token) -> Result<Cow<'de, str>, JsonError> {
+        Parser::decode_string(token, &self.parser)
+    }
+
+    fn key(&mut self) -> Result<Cow<'de, str>, JsonError> {
+        let token = self.next("a string key")?;
+        match token.kind {
+            TokenKind::String => self.string(&token),
+            _ => Err(Parser::unexpected(&token, &self.parser, "a string key")),
+        }
+    }
+
+    // Integers too wide for 64 bits are only given to types that ask for them,
+    // as other visitors, such as that of `f64`, reject 128-bit values.
+    // Anything else is read as by `deserialize_any`.
+    fn wide_integer<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, JsonError> {
+        if let TokenKind::Number = self.peek("a value")? {
+            let token = self.next("a value")?;
+            let text = Parser::resolve_string(&token, self.parser.input);
+            if let Ok(n) = text.parse::<u128>() {
+                return visitor.visit_u128(n).map_err(|err| self.locate(err));
+            }
+            if let Ok(n) = text.parse::<i128>() {
+                return visitor.visit_i128(n).map_err(|err| self.locate(err));
+            }
+            self.peeked = Some(token);
+        }
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = JsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        let token = self.next("a value")?;
+        let value = match token.kind {
+            TokenKind::Null => visitor.visit_unit(),
+            TokenKind::True => visitor.visit_bool(true),
+            TokenKind::False => visitor.visit_bool(false),
+            TokenKind::Number => {
+                let number = Parser::resolve_number(&token, &self.parser)?;
+                if let Some(n) = number.as_u64() {
+                    visitor.visit_u64(n)
+                } else if let Some(n) = number.as_i64() {
+                    visitor.visit_i64(n)
+                } else {
+                    visitor.visit_f64(number.as_f64())
+                }
+            }
+            TokenKind::String => match self.string(&token)? {
+                Cow::Borrowed(str) => visitor.visit_borrowed_str(str),
+                Cow::Owned(string) => visitor.visit_string(string),
+            },
+            TokenKind::LeftBracket => {
+                self.enter()?;
+                visitor
+                    .visit_seq(Elements::new(self))
+                    .and_then(|value| self.close(TokenKind::RightBracket).map(|()| value))
+            }
+            TokenKind::LeftBrace => {
+                self.enter()?;
+                visitor
+                    .visit_map(Entries::new(self))
+                    .and_then(|value| self.close(TokenKind::RightBrace).map(|()| value))
+            }
+            _ => Err(Parser::unexpected(&token, &self.parser, "a value")),
+        };
+        value.map_err(|err| self.locate(err))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        match self.peek("a value")? {
+            TokenKind::Null => {
+                self.next("a value")?;
+                visitor.visit_none().map_err(|err| self.locate(err))
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        if name == NUMBER_TOKEN {
+            return self.wide_integer(visitor);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Enums are externally tagged: a unit variant is its name, any other
+    // variant an object with the name as its only key.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        match self.peek("a value")? {
+            TokenKind::String => visitor
+                .visit_enum(UnitVariant { de: self })
+                .map_err(|err| self.locate(err)),
+            TokenKind::LeftBrace => {
+                self.next("a value")?;
+                self.enter()?;
+                let value = visitor
+                    .visit_enum(Variant { de: self })
+                    .map_err(|err| self.locate(err))?;
+                self.close(TokenKind::RightBrace)?;
+                Ok(value)
+            }
+            _ => {
+                let token = self.next("a value")?;
+                Err(Parser::unexpected(
+                    &token,
+                    &self.parser,
+                    "a string or an object",
+                ))
+            }
+        }
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        self.wide_integer(visitor)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        self.wide_integer(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+// The elements of an array, after its opening bracket.
+struct Elements<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+}
+
+impl<'a, 'de> Elements<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Elements<'a, 'de> {
+        Elements { de, first: true }
+    }
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_, 'de> {
+    type Error = JsonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, JsonError> {
+        let expected = if self.first {
+            "a value or ']'"
+        } else {
+            "',' or ']'"
+        };
+
+        match self.de.peek(expected)? {
+            TokenKind::RightBracket => return Ok(None),
+            TokenKind::Comma if !self.first => {
+                self.de.next(expected)?;
+            }
+            _ if self.first => {}
+            _ => {
+                let token = self.de.next(expected)?;
+                return Err(Parser::unexpected(&token, &self.de.parser, expected));
+            }
+        }
+
+        self.first = false;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+// The members of an object, after its opening brace.
+struct Entries<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+    // The keys read so far, to reject repeats as `parse` does by default.
+    keys: HashSet<Cow<'de, str>>,
+}
+
+impl<'a, 'de> Entries<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Entries<'a, 'de> {
+        Entries {
+            de,
+            first: true,
+            keys: HashSet::new(),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries<'_, 'de> {
+    type Error = JsonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, JsonError> {
+        let expected = if self.first {
+            "a string key or '}'"
+        } else {
+            "',' or '}'"
+        };
+
+        match self.de.peek(expected)? {
+            TokenKind::RightBrace => return Ok(None),
+            TokenKind::Comma if !self.first => {
+                self.de.next(expected)?;
+            }
+            _ if self.first => {}
+            _ => {
+                let token = self.de.next(expected)?;
+                return Err(Parser::unexpected(&token, &self.de.parser, expected));
+            }
+        }
+
+        self.first = false;
+        let key = self.de.key()?;
+        if self.de.parser.options.duplicate_keys == DuplicateKeys::Error
+            && !self.keys.insert(key.clone())
+        {
+            return Err(JsonError::DuplicateKey {
+                key: key.into_owned(),
+                span: self.de.parser.lexer.last_token_span(),
+            });
+        }
+
+        let key = seed.deserialize(Key { de: self.de, key })?;
+        Parser::expect_colon(&mut self.de.parser)?;
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, JsonError> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+// An object key. Keys are always strings, but may be read as integers for
+// maps such as `HashMap<u32, T>`.
+struct Key<'a, 'de> {
+    de: &'a Deserializer<'de>,
+    key: Cow<'de, str>,
+}
+
+macro_rules! integer_key {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+                let value = match self.key.parse::<$ty>() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&self.key), &visitor)),
+                };
+                value.map_err(|err| self.de.locate(err))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Key<'_, 'de> {
+    type Error = JsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        let value = match self.key {
+            Cow::Borrowed(str) => visitor.visit_borrowed_str(str),
+            Cow::Owned(string) => visitor.visit_string(string),
+        };
+        value.map_err(|err| self.de.locate(err))
+    }
+
+    integer_key! {
+        deserialize_i8 => visit_i64(i64),
+        deserialize_i16 => visit_i64(i64),
+        deserialize_i32 => visit_i64(i64),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u64(u64),
+        deserialize_u16 => visit_u64(u64),
+        deserialize_u32 => visit_u64(u64),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u128 => visit_u128(u128),
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf option unit
+        unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+// A unit variant written as its name.
+struct UnitVariant<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for UnitVariant<'_, 'de> {
+    type Error = JsonError;
+    type Variant = Self;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<(T::Value, Self), JsonError> {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for UnitVariant<'_, 'de> {
+    type Error = JsonError;
+
+    fn unit_variant(self) -> Result<(), JsonError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        _seed: T,
+    ) -> Result<T::Value, JsonError> {
+        Err(de::Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"newtype variant",
+        ))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        Err(de::Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        Err(de::Error::invalid_type(
+            Unexpected::UnitVariant,
+            &"struct variant",
+        ))
+    }
+}
+
+// A variant written as an object with its name as the only key, after the
+// opening brace.
+struct Variant<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'_, 'de> {
+    type Error = JsonError;
+    type Variant = Self;
+
+    fn variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<(T::Value, Self), JsonError> {
+        let key = self.de.key()?;
+        let variant = seed.deserialize(Key { de: self.de, key })?;
+        Parser::expect_colon(&mut self.de.parser)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'_, 'de> {
+    type Error = JsonError;
+
+    fn unit_variant(self) -> Result<(), JsonError> {
+        Deserialize::deserialize(&mut *self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, JsonError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        de::Deserializer::deserialize_any(&mut *self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        de::Deserializer::deserialize_any(&mut *self.de, visitor)
+    }
+}
+
+// The name of a newtype struct that stands for a number in the text.
+//
+// `JsonValue` and `Number` deserialise through it to be given integers too
+// wide for 64 bits, which `deserialize_any` cannot give every visitor. Other
+// deserializers see a newtype struct and visit its contents as usual.
+//
+// When serialising, a number with raw text that neither a 128-bit integer nor
+// an `f64` would write back exactly passes the text through it, and
+// `to_string` writes that text without quotes.
+pub(crate) const NUMBER_TOKEN: &str = "$rsjson::private::Number";
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_newtype_struct(NUMBER_TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Boolean(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n.into()))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n.into()))
+    }
+
+    fn visit_i128<E>(self, n: i128) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n.into()))
+    }
+
+    fn visit_u128<E>(self, n: u128) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n.into()))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n.into()))
+    }
+
+    fn visit_str<E>(self, str: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(str.to_string()))
+    }
+
+    fn visit_string<E>(self, string: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(string))
+    }
+
+    fn visit_unit<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        JsonValue::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(JsonValue::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<JsonValue, A::Error> {
+        MapVisitor(std::marker::PhantomData)
+            .visit_map(map)
+            .map(JsonValue::Object)
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Number, D::Error> {
+        deserializer.deserialize_newtype_struct(NUMBER_TOKEN, NumberVisitor)
+    }
+}
+
+struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = Number;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a number")
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Number, E> {
+        Ok(n.into())
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Number, E> {
+        Ok(n.into())
+    }
+
+    fn visit_i128<E>(self, n: i128) -> Result<Number, E> {
+        Ok(n.into())
+    }
+
+    fn visit_u128<E>(self, n: u128) -> Result<Number, E> {
+        Ok(n.into())
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Number, E> {
+        Ok(n.into())
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Number, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for Map<String, V> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor(std::marker::PhantomData))
+    }
+}
+
+struct MapVisitor<V>(std::marker::PhantomData<V>);
+
+impl<'de, V: Deserialize<'de>> Visitor<'de> for MapVisitor<V> {
+    type Value = Map<String, V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an object")
+    }
+
+    // `from_str` rejects a repeated key before it gets here, but with other
+    // deserializers the last value of the key wins.
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Map<String, V>, A::Error> {
+        let mut map = Map::new();
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_str, ErrorKind, JsonValue, Map};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Service<'a> {
+        name: &'a str,
+        port: u16,
+        tags: Vec<String>,
+        owner: Option<String>,
+        #[serde(default)]
+        replicas: u32,
+        mode: Mode,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Mode {
+        Off,
+        Fixed(u32),
+        Range { min: u32, max: u32 },
+    }
+
+    #[test]
+    fn deserialises_structs_and_enums() {
+        let service: Service = from_str(
+            r#"{"name": "api", "port": 8080, "tags": ["web", "a\nb"], "owner": null,
+                "mode": {"Range": {"min": 1, "max": 3}}, "unknown": [{"x": 1}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            service,
+            Service {
+                name: "api",
+                port: 8080,
+                tags: vec!["web".to_string(), "a\nb".to_string()],
+                owner: None,
+                replicas: 0,
+                mode: Mode::Range { min: 1, max: 3 },
+            }
+        );
+
+        assert_eq!(from_str::<Mode>(r#""Off""#).unwrap(), Mode::Off);
+        assert_eq!(from_str::<Mode>(r#"{"Fixed": 2}"#).unwrap(), Mode::Fixed(2));
+        assert_eq!(
+            from_str::<Vec<Option<f64>>>("[1, null, -2.5]").unwrap(),
+            vec![Some(1.0), None, Some(-2.5)]
+        );
+    }
+
+    #[test]
+    fn reads_integer_keys() {
+        let map: HashMap<u32, bool> = from_str(r#"{"1": true, "20": false}"#).unwrap();
+        assert!(map[&1] && !map[&20]);
+
+        let err = from_str::<HashMap<u32, bool>>(r#"{"x": true}"#).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Custom);
+        assert_eq!(err.span().unwrap().col, 2);
+    }
+
+    #[test]
+    fn deserialises_values() {
+        let text = r#"{"a": [1, -2, 3.5, "s", true, null], "b": {}}"#;
+        let value: JsonValue = from_str(text).unwrap();
+        assert_eq!(value, crate::parse(text).unwrap());
+        assert_eq!(value, json!({"a": [1, -2, 3.5, "s", true, null], "b": {}}));
+
+        let map: Map<String, u8> = from_str(r#"{"b": 1, "a": 2}"#).unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a"]);
+    }
+
+    #[test]
+    fn rejects_duplicate_keys_like_parse() {
+        let text = "{\"a\": 1,\n \"a\": 2}";
+        let parsed = crate::parse(text).unwrap_err();
+        for err in [
+            from_str::<JsonValue>(text).unwrap_err(),
+            from_str::<Map<String, u8>>(text).unwrap_err(),
+            from_str::<HashMap<String, u8>>(text).unwrap_err(),
+        ] {
+            assert_eq!(err.kind(), ErrorKind::DuplicateKey);
+            assert_eq!(err.span(), parsed.span());
+            assert_eq!(err.to_string(), parsed.to_string());
+        }
+
+        let err = from_str::<HashMap<u8, u8>>(r#"{"1": 1, "1": 2}"#).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DuplicateKey);
+    }
+
+    #[test]
+    fn errors_are_positioned() {
+        let err = from_str::<Service>("{\n  \"name\": \"api\",\n  \"port\": 70000\n}").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Custom);
+        assert_eq!((err.span().unwrap().line, err.span().unwrap().col), (3, 11));
+        assert_eq!(
+            err.to_string(),
+            "invalid value: integer `70000`, expected u16 at line 3, col 11"
+        );
+
+        let err = from_str::<Service>(r#"{"name": "api"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `port` at line 1, col 15");
+
+        let err = from_str::<Vec<u8>>("[1 2]").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+        assert_eq!(err.span().unwrap().col, 4);
+
+        let err = from_str::<Vec<u8>>("[1] x").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TrailingCharacters);
+
+        let err = from_str::<(u8, u8)>("[1, 2, 3]").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+        assert_eq!(err.to_string(), "expected ']', found ',' at line 1, col 6");
+
+        let err = from_str::<Service>(r#"{"name": "api", "port": 1]"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected ',' or '}', found ']' at line 1, col 26"
+        );
+
+        let deep = "[".repeat(200) + &"]".repeat(200);
+        let err = from_str::<JsonValue>(&deep).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DepthLimitExceeded);
+    }
+
+    #[test]
+    fn borrows_strings_without_escapes() {
+        let input = r#"["plain", "esc\"aped"]"#;
+        let strs: Vec<std::borrow::Cow<str>> = from_str(input).unwrap();
+        assert_eq!(strs, ["plain", "esc\"aped"]);
+
+        let err = from_str::<Vec<&str>>(input).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Custom);
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{JsonValue, Map};
+use std::fmt;
+
+/// A position in the input. `offset` is in bytes, `line` and `col` start at 1
+/// and `col` is counted in the [`ColumnUnit`](crate::ColumnUnit) the input was
+/// parsed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    /// A token that is valid JSON on its own but not at this position.
+    /// `found` and `expected` describe the tokens, e.g. `','` or `a value`.
+    UnexpectedToken {
+        found: &'static str,
+        expected: &'static str,
+        span: Span,
+    },
+    /// The input ended while `expected` was still required.
+    UnexpectedEof {
+        expected: &'static str,
+        span: Span,
+    },
+    InvalidNumber {
+        span: Span,
+    },
+    InvalidUtf8 {
+        span: Span,
+    },
+    DuplicateKey {
+        key: String,
+        span: Span,
+    },
+    DepthLimitExceeded {
+        limit: usize,
+        span: Span,
+    },
+    InvalidEscape {
+        span: Span,
+    },
+    LoneSurrogate {
+        span: Span,
+    },
+    UnterminatedString {
+        span: Span,
+    },
+    TruncatedLiteral {
+        span: Span,
+    },
+    UnexpectedCharacter {
+        ch: char,
+        span: Span,
+    },
+    TrailingCharacters {
+        span: Span,
+    },
+    /// A message from a type being converted through serde, such as a
+    /// missing field. Deserialising places it at the token being read; errors
+    /// raised while serialising have no position.
+    Custom {
+        message: String,
+        span: Option<Span>,
+    },
+}
+
+impl JsonError {
+    /// Where the error was detected, which is only unknown for
+    /// [`Custom`](JsonError::Custom) errors raised while serialising.
+    pub fn span(&self) -> Option<Span> {
+        let span = match self {
+            JsonError::UnexpectedToken { span, .. }
+            | JsonError::UnexpectedEof { span, .. }
+            | JsonError::InvalidNumber { span }
+            | JsonError::InvalidUtf8 { span }
+            | JsonError::DuplicateKey { span, .. }
+            | JsonError::DepthLimitExceeded { span, .. }
+            | JsonError::InvalidEscape { span }
+            | JsonError::LoneSurrogate { span }
+            | JsonError::UnterminatedString { span }
+            | JsonError::TruncatedLiteral { span }
+            | JsonError::UnexpectedCharacter { span, .. }
+            | JsonError::TrailingCharacters { span } => span,
+            JsonError::Custom { span, .. } => return *span,
+        };
+        Some(*span)
+    }
+
+    /// What kind of problem the error reports.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            JsonError::UnexpectedToken { .. } => ErrorKind::UnexpectedToken,
+            JsonError::UnexpectedEof { .. } => ErrorKind::UnexpectedEof,
+            JsonError::InvalidNumber { .. } => ErrorKind::InvalidNumber,
+            JsonError::InvalidUtf8 { .. } => ErrorKind::InvalidUtf8,
+            JsonError::DuplicateKey { .. } => ErrorKind::DuplicateKey,
+            JsonError::DepthLimitExceeded { .. } => ErrorKind::DepthLimitExceeded,
+            JsonError::InvalidEscape { .. } => ErrorKind::InvalidEscape,
+            JsonError::LoneSurrogate { .. } => ErrorKind::LoneSurrogate,
+            JsonError::UnterminatedString { .. } => ErrorKind::UnterminatedString,
+            JsonError::TruncatedLiteral { .. } => ErrorKind::TruncatedLiteral,
+            JsonError::UnexpectedCharacter { .. } => ErrorKind::UnexpectedCharacter,
+            JsonError::TrailingCharacters { .. } => ErrorKind::TrailingCharacters,
+            JsonError::Custom { .. } => ErrorKind::Custom,
+        }
+    }
+
+    /// The stable code of the error's kind, see [`ErrorKind::code`].
+    pub fn code(&self) -> &'static str {
+        self.kind().code()
+    }
+
+    /// The error as a JSON object for tools, in the form
+    /// `{"code": "E0001", "message": "...", "span": {"offset": 3, "line": 1, "col": 4}}`.
+    /// `span` is `null` when the error has no position.
+    pub fn to_json(&self) -> JsonValue {
+        let span = match self.span() {
+            Some(span) => JsonValue::Object(Map::from([
+                ("offset".to_string(), JsonValue::Number(span.offset.into())),
+                ("line".to_string(), JsonValue::Number(span.line.into())),
+                ("col".to_string(), JsonValue::Number(span.col.into())),
+            ])),
+            None => JsonValue::Null,
+        };
+
+        JsonValue::Object(Map::from([
+            (
+                "code".to_string(),
+                JsonValue::String(self.code().to_string()),
+            ),
+            ("message".to_string(), JsonValue::String(self.message())),
+            ("span".to_string(), span),
+        ]))
+    }
+
+    /// The description of the error without its position.
+    pub fn message(&self) -> String {
+        Message(self).to_string()
+    }
+
+    /// Renders the error against the `source` it was parsed from, showing the
+    /// offending line with a caret under the error and the lines around it.
+    /// An error without a position shows only its first line.
+    ///
+    /// ```text
+    /// error[E0001]: expected ',' or ']', found a number
+    ///  --> line 2, col 5
+    ///   |
+    /// 1 | [
+    /// 2 |   1 2
+    ///   |     ^ expected ',' or ']'
+    /// 3 | ]
+    ///   |
+    /// ```
+    pub fn render<'a>(&'a self, source: &'a str) -> Rendered<'a> {
+        Rendered {
+            error: self,
+            source,
+            color: false,
+        }
+    }
+
+    // The note printed next to the caret.
+    fn label(&self) -> Option<String> {
+        match self {
+            JsonError::UnexpectedToken { expected, .. }
+            | JsonError::UnexpectedEof { expected, .. } => Some(format!("expected {expected}")),
+            _ => None,
+        }
+    }
+}
+
+/// What kind of problem a [`JsonError`] reports, without its details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    UnexpectedToken,
+    UnexpectedEof,
+    InvalidNumber,
+    InvalidUtf8,
+    DuplicateKey,
+    DepthLimitExceeded,
+    InvalidEscape,
+    LoneSurrogate,
+    UnterminatedString,
+    TruncatedLiteral,
+    UnexpectedCharacter,
+    TrailingCharacters,
+    Custom,
+}
+
+impl ErrorKind {
+    /// A code that identifies the kind across releases, such as `"E0001"`.
+    /// Codes are never renumbered or reused, so tools can match on them
+    /// instead of on messages, which may change.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedToken => "E0001",
+            ErrorKind::UnexpectedEof => "E0002",
+            ErrorKind::InvalidNumber => "E0003",
+            ErrorKind::InvalidUtf8 => "E0004",
+            ErrorKind::DuplicateKey => "E0005",
+            ErrorKind::DepthLimitExceeded => "E0006",
+            ErrorKind::InvalidEscape => "E0007",
+            ErrorKind::LoneSurrogate => "E0008",
+            ErrorKind::UnterminatedString => "E0009",
+            ErrorKind::TruncatedLiteral => "E0010",
+            ErrorKind::UnexpectedCharacter => "E0011",
+            ErrorKind::TrailingCharacters => "E0012",
+            ErrorKind::Custom => "E0013",
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(span) = self.span() else {
+            return write!(f, "{}", Message(self));
+        };
+
+        write!(
+            f,
+            "{} at line {}, col {}",
+            Message(self),
+            span.line,
+            span.col
+        )
+    }
+}
+
+/// A problem found by [`parse_with_recovery`](crate::parse_with_recovery),
+/// which reports it and carries on parsing.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub error: JsonError,
+}
+
+impl Diagnostic {
+    /// Where the problem was detected, see [`JsonError::span`].
+    pub fn span(&self) -> Option<Span> {
+        self.error.span()
+    }
+
+    /// The diagnostic as a JSON object, see [`JsonError::to_json`].
+    pub fn to_json(&self) -> JsonValue {
+        self.error.to_json()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+struct Message<'a>(&'a JsonError);
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            JsonError::UnexpectedToken {
+                found, expected, ..
+            } => write!(f, "expected {expected}, found {found}"),
+            JsonError::UnexpectedEof { expected, .. } => {
+                write!(f, "unexpected end of input, expected {expected}")
+            }
+            JsonError::InvalidNumber { .. } => write!(f, "invalid number"),
+            JsonError::InvalidUtf8 { .. } => write!(f, "invalid utf-8"),
+            JsonError::DuplicateKey { key, .. } => write!(f, "duplicate key {key:?}"),
+            JsonError::DepthLimitExceeded { limit, .. } => {
+                write!(f, "nesting deeper than {limit} levels")
+            }
+            JsonError::InvalidEscape { .. } => write!(f, "invalid escape sequence"),
+            JsonError::LoneSurrogate { .. } => write!(f, "unpaired surrogate escape"),
+            JsonError::UnterminatedString { .. } => write!(f, "unterminated string"),
+            JsonError::TruncatedLiteral { .. } => write!(f, "truncated literal"),
+            JsonError::UnexpectedCharacter { ch, .. } => write!(f, "unexpected character {ch:?}"),
+            JsonError::TrailingCharacters { .. } => write!(f, "trailing characters"),
+            JsonError::Custom { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+/// A [`JsonError`] shown against its source, created by [`JsonError::render`].
+/// Formatting it with `{}` prints the report.
+pub struct Rendered<'a> {
+    error: &'a JsonError,
+    source: &'a str,
+    color: bool,
+}
+
+impl Rendered<'_> {
+    // Lines shown before and after the offending line.
+    const CONTEXT_LINES: usize = 2;
+
+    /// Highlights the report with ANSI escape codes, for terminals.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.color {
+            code
+        } else {
+            ""
+        }
+    }
+}
+
+// Splits `source` the way the lexer counts lines, where "\n", "\r\n" and a
+// lone "\r" all end a line. Returns the byte offset and text of each line.
+fn source_lines(source: &str) -> Vec<(usize, &str)> {
+    let bytes = source.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                lines.push((start, &source[start..i]));
+                start = i + 1;
+            }
+            b'\r' => {
+                lines.push((start, &source[start..i]));
+                if bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    lines.push((start, &source[start..]));
+    lines
+}
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let red = self.paint("\x1b[1;31m");
+        let blue = self.paint("\x1b[1;34m");
+        let bold = self.paint("\x1b[1m");
+        let reset = self.paint("\x1b[0m");
+
+        write!(
+            f,
+            "{red}error[{}]{reset}{bold}: {}{reset}",
+            self.error.code(),
+            Message(self.error)
+        )?;
+        let Some(span) = self.error.span() else {
+            return Ok(());
+        };
+        writeln!(f)?;
+
+        // The line is found from the byte offset, which unlike `col` does not
+        // depend on the column unit the input was parsed with.
+        let lines = source_lines(self.source);
+        let offset = span.offset.min(self.source.len());
+        let index = lines
+            .iter()
+            .rposition(|(start, _)| *start <= offset)
+            .unwrap_or(0);
+
+        let first = index.saturating_sub(Self::CONTEXT_LINES);
+        let last = (index + Self::CONTEXT_LINES).min(lines.len() - 1);
+        let width = (last + 1).to_string().len();
+        let pad = " ".repeat(width);
+
+        writeln!(
+            f,
+            "{pad}{blue}-->{reset} line {}, col {}",
+            span.line, span.col
+        )?;
+        writeln!(f, "{pad} {blue}|{reset}")?;
+
+        for (i, (start, text)) in lines.iter().enumerate().take(last + 1).skip(first) {
+            writeln!(f, "{blue}{:>width$} |{reset} {text}", i + 1)?;
+            if i != index {
+                continue;
+            }
+
+            // Tabs are kept so the caret lines up however wide they are shown.
+            let mut end = (offset - start).min(text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let indent: String = text[..end]
+                .chars()
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            write!(f, "{pad} {blue}|{reset} {indent}{red}^")?;
+            if let Some(label) = self.error.label() {
+                write!(f, " {label}")?;
+            }
+            writeln!(f, "{reset}")?;
+        }
+
+        write!(f, "{pad} {blue}|{reset}")
+    }
+}
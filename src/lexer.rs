@@ -1,14 +1,18 @@
+use crate::{ColumnUnit, JsonError, Span};
+
 #[derive(Debug)]
 pub(crate) struct Lexer {
-    pub(crate) pos: u32,
-    pub(crate) line: u32,
-    pub(crate) col: u32,
-    pub(crate) last_token_line: u32,
-    pub(crate) last_token_col: u32,
+    pub(crate) pos: usize,
+    pub(crate) line: usize,
+    pub(crate) col: usize,
+    pub(crate) last_token_line: usize,
+    pub(crate) last_token_col: usize,
+    pub(crate) last_token_offset: usize,
+    pub(crate) column_unit: ColumnUnit,
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum TokenKind {
     LeftBrace,
     RightBrace,
@@ -23,252 +27,402 @@ pub(crate) enum TokenKind {
     Null,
 }
 
+impl TokenKind {
+    // How the token is named in error messages.
+    pub(crate) fn describe(&self) -> &'static str {
+        match self {
+            TokenKind::LeftBrace => "'{'",
+            TokenKind::RightBrace => "'}'",
+            TokenKind::LeftBracket => "'['",
+            TokenKind::RightBracket => "']'",
+            TokenKind::String => "a string",
+            TokenKind::Number => "a number",
+            TokenKind::Colon => "':'",
+            TokenKind::Comma => "','",
+            TokenKind::True => "'true'",
+            TokenKind::False => "'false'",
+            TokenKind::Null => "'null'",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Token {
     pub(crate) kind: TokenKind,
-    pub(crate) start: u32,
-    pub(crate) end: u32,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
 }
 
 impl Lexer {
     pub fn new() -> Lexer {
+        Lexer::with_column_unit(ColumnUnit::default())
+    }
+
+    pub fn with_column_unit(column_unit: ColumnUnit) -> Lexer {
         Lexer {
             pos: 0,
             line: 1,
             col: 1,
             last_token_line: 1,
             last_token_col: 1,
+            last_token_offset: 0,
+            column_unit,
         }
     }
 
-    pub fn next_token(&mut self, input: &[u8]) -> Option<Token> {
-        loop {
-            if self.pos as usize >= input.len() {
-                return None;
+    fn peek(&self, input: &[u8]) -> Option<u8> {
+        input.get(self.pos).copied()
+    }
+
+    // The current position, just past the last token or whitespace read.
+    pub(crate) fn span(&self) -> Span {
+        Span {
+            offset: self.pos,
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    pub(crate) fn last_token_span(&self) -> Span {
+        Span {
+            offset: self.last_token_offset,
+            line: self.last_token_line,
+            col: self.last_token_col,
+        }
+    }
+
+    fn number_error(&self) -> JsonError {
+        JsonError::InvalidNumber { span: self.span() }
+    }
+
+    fn consume_digits(&mut self, input: &[u8]) -> Result<(), JsonError> {
+        if !matches!(self.peek(input), Some(b'0'..=b'9')) {
+            return Err(self.number_error());
+        }
+
+        while let Some(b'0'..=b'9') = self.peek(input) {
+            self.pos += 1;
+            self.col += 1;
+        }
+
+        Ok(())
+    }
+
+    // number = [ minus ] int [ frac ] [ exp ]  (RFC 8259, section 6)
+    fn lex_number(&mut self, input: &[u8]) -> Result<Token, JsonError> {
+        let init_pos: usize = self.pos;
+
+        if let Some(b'-') = self.peek(input) {
+            self.pos += 1;
+            self.col += 1;
+        }
+
+        match self.peek(input) {
+            Some(b'0') => {
+                self.pos += 1;
+                self.col += 1;
             }
+            _ => self.consume_digits(input)?,
+        }
 
-            self.last_token_col = self.col;
-            self.last_token_line = self.line;
+        if let Some(b'.') = self.peek(input) {
+            self.pos += 1;
+            self.col += 1;
+            self.consume_digits(input)?;
+        }
 
-            match input[self.pos as usize] {
-                b'\n' => {
-                    self.pos += 1;
-                    self.line += 1;
-                    self.col = 1;
+        if let Some(b'e' | b'E') = self.peek(input) {
+            self.pos += 1;
+            self.col += 1;
 
-                    continue;
-                }
-                b' ' | b'\t' => {
-                    self.pos += 1;
-                    self.col += 1;
+            if let Some(b'+' | b'-') = self.peek(input) {
+                self.pos += 1;
+                self.col += 1;
+            }
+            self.consume_digits(input)?;
+        }
 
-                    continue;
-                }
-                b'{' => {
-                    let init_pos = self.pos;
+        // Anything that could continue a number here (a leading zero followed by
+        // digits, a second fraction or sign) makes the literal malformed.
+        if let Some(b'.' | b'+' | b'-' | b'E' | b'e' | b'0'..=b'9') = self.peek(input) {
+            return Err(self.number_error());
+        }
 
-                    self.pos += 1;
-                    self.col += 1;
+        Ok(Token {
+            kind: TokenKind::Number,
+            start: init_pos,
+            end: self.pos,
+        })
+    }
 
-                    return Some(Token {
-                        kind: TokenKind::LeftBrace,
-                        start: init_pos,
-                        end: self.pos,
-                    });
-                }
-                b'}' => {
-                    let init_pos = self.pos;
+    // Decodes the character starting at the current position, or returns
+    // `None` if the bytes there are not valid UTF-8.
+    fn current_char(&self, input: &[u8]) -> Option<char> {
+        let start = self.pos;
+        let end = input.len().min(start + 4);
 
-                    self.pos += 1;
-                    self.col += 1;
+        (start + 1..=end)
+            .find_map(|end| std::str::from_utf8(&input[start..end]).ok())
+            .and_then(|str| str.chars().next())
+    }
 
-                    return Some(Token {
-                        kind: TokenKind::RightBrace,
-                        start: init_pos,
-                        end: self.pos,
-                    });
-                }
-                b'[' => {
-                    let init_pos = self.pos;
+    fn invalid_utf8(&self) -> JsonError {
+        JsonError::InvalidUtf8 { span: self.span() }
+    }
 
+    fn unexpected_character(&self, input: &[u8]) -> JsonError {
+        match self.current_char(input) {
+            Some(ch) => JsonError::UnexpectedCharacter {
+                ch,
+                span: self.span(),
+            },
+            None => self.invalid_utf8(),
+        }
+    }
+
+    // Matches a keyword at the current position. Input that ends part way
+    // through the keyword is reported as truncated rather than read past, and
+    // the first byte that does not match is reported as unexpected.
+    fn lex_literal(
+        &mut self,
+        input: &[u8],
+        literal: &[u8],
+        kind: TokenKind,
+    ) -> Result<Token, JsonError> {
+        let init_pos: usize = self.pos;
+
+        for expected in literal {
+            match self.peek(input) {
+                Some(byte) if byte == *expected => {
                     self.pos += 1;
                     self.col += 1;
-
-                    return Some(Token {
-                        kind: TokenKind::LeftBracket,
-                        start: init_pos,
-                        end: self.pos,
+                }
+                Some(_) => return Err(self.unexpected_character(input)),
+                None => {
+                    return Err(JsonError::TruncatedLiteral {
+                        span: self.last_token_span(),
                     });
                 }
-                b']' => {
-                    let init_pos = self.pos;
+            }
+        }
 
-                    self.pos += 1;
-                    self.col += 1;
+        Ok(Token {
+            kind,
+            start: init_pos,
+            end: self.pos,
+        })
+    }
 
-                    return Some(Token {
-                        kind: TokenKind::RightBracket,
-                        start: init_pos,
-                        end: self.pos,
-                    });
-                }
-                b':' => {
-                    let init_pos = self.pos;
+    fn lex_string(&mut self, input: &[u8]) -> Result<Token, JsonError> {
+        //Consume the quote
+        self.col += 1;
+        self.pos += 1;
 
-                    self.pos += 1;
-                    self.col += 1;
+        let str_start: usize = self.pos;
 
-                    return Some(Token {
-                        kind: TokenKind::Colon,
-                        start: init_pos,
-                        end: self.pos,
+        loop {
+            match self.peek(input) {
+                None => {
+                    return Err(JsonError::UnterminatedString {
+                        span: self.last_token_span(),
                     });
                 }
-                b',' => {
-                    let init_pos = self.pos;
-
-                    self.pos += 1;
+                Some(b'"') => break,
+                Some(0x00..=0x1F) => return Err(self.unexpected_character(input)),
+                Some(b'\\') => {
+                    // Skip an escaped quote or backslash so it does not end the
+                    // string. The parser validates the escape itself.
                     self.col += 1;
+                    self.pos += 1;
 
-                    return Some(Token {
-                        kind: TokenKind::Comma,
-                        start: init_pos,
-                        end: self.pos,
-                    });
-                }
-                b'n' => {
-                    let init_pos = self.pos;
-
-                    if &input[self.pos as usize..self.pos as usize + 4] == b"null" {
-                        self.pos += 4;
-                        self.col += 4;
-
-                        return Some(Token {
-                            kind: TokenKind::Null,
-                            start: init_pos,
-                            end: self.pos,
-                        });
-                    } else {
-                        self.pos += 1;
+                    if let Some(b'"' | b'\\') = self.peek(input) {
                         self.col += 1;
-                    }
-                }
-                b't' => {
-                    let init_pos: u32 = self.pos;
-
-                    if &input[self.pos as usize..self.pos as usize + 4] == b"true" {
-                        self.pos += 4;
-                        self.col += 4;
-
-                        return Some(Token {
-                            kind: TokenKind::True,
-                            start: init_pos,
-                            end: self.pos,
-                        });
-                    } else {
                         self.pos += 1;
-                        self.col += 1;
                     }
                 }
-                b'f' => {
-                    let init_pos: u32 = self.pos;
-
-                    if &input[self.pos as usize..self.pos as usize + 5] == b"false" {
-                        self.pos += 5;
-                        self.col += 5;
-
-                        return Some(Token {
-                            kind: TokenKind::False,
-                            start: init_pos,
-                            end: self.pos,
-                        });
-                    } else {
-                        self.pos += 1;
-                        self.col += 1;
-                    }
+                Some(0x80..=0xFF) => {
+                    // String tokens are validated here so the parser can treat
+                    // their contents as `str` without checking again.
+                    let ch = self.current_char(input).ok_or(self.invalid_utf8())?;
+
+                    self.col += self.column_unit.width(ch);
+                    self.pos += ch.len_utf8();
                 }
-                b'"' => {
-                    //Consume the quote
+                Some(_) => {
                     self.col += 1;
                     self.pos += 1;
+                }
+            }
+        }
 
-                    let mut str_end: u32;
-                    let mut last_byte: u8 = b'\0';
-                    let str_start: u32 = self.pos;
-
-                    loop {
-                        if input[self.pos as usize] == b'"' {
-                            //Consume the quote
-                            str_end = self.pos;
-                            self.col += 1;
-                            self.pos += 1;
-
-                            if last_byte != b'\\' {
-                                break;
-                            } else {
-                                let mut back_slash_count: i32 = 0;
-                                let mut last_byte_idx: u32 = self.pos - 2;
-
-                                loop {
-                                    if input[last_byte_idx as usize] == b'\\' {
-                                        back_slash_count += 1;
-                                    } else {
-                                        break;
-                                    }
-
-                                    last_byte_idx -= 1;
-                                }
-
-                                if back_slash_count % 2 != 0 {
-                                    continue;
-                                } else {
-                                    break;
-                                }
-                            }
-                        }
-
-                        last_byte = input[self.pos as usize];
-                        self.col += 1;
+        let str_end: usize = self.pos;
+
+        //Consume the quote
+        self.col += 1;
+        self.pos += 1;
+
+        Ok(Token {
+            kind: TokenKind::String,
+            start: str_start,
+            end: str_end,
+        })
+    }
+
+    pub fn skip_whitespace(&mut self, input: &[u8]) {
+        while let Some(byte) = self.peek(input) {
+            match byte {
+                b'\n' => {
+                    self.pos += 1;
+                    self.line += 1;
+                    self.col = 1;
+                }
+                b'\r' => {
+                    // "\r\n" and a lone "\r" both end a line.
+                    self.pos += 1;
+                    if let Some(b'\n') = self.peek(input) {
                         self.pos += 1;
                     }
+                    self.line += 1;
+                    self.col = 1;
+                }
+                b' ' | b'\t' => {
+                    self.pos += 1;
+                    self.col += 1;
+                }
+                _ => break,
+            }
+        }
+    }
 
-                    return Some(Token {
-                        kind: TokenKind::String,
-                        start: str_start,
-                        end: str_end,
-                    });
+    // Skips the rest of a token that failed to lex so lexing can resume after
+    // the error. A string is skipped up to its closing quote or the end of its
+    // line, anything else up to the next whitespace or structural character.
+    pub(crate) fn skip_invalid(&mut self, input: &[u8]) {
+        let in_string = input.get(self.last_token_offset) == Some(&b'"');
+        let mut escaped = false;
+
+        // Tokens never fail on their first byte when it is whitespace or
+        // structural, so this always makes progress.
+        while let Some(byte) = self.peek(input) {
+            match byte {
+                b'\n' | b'\r' => break,
+                b'"' if in_string && !escaped => {
+                    self.pos += 1;
+                    self.col += 1;
+                    break;
                 }
-                b'0'..=b'9' | b'-' => {
-                    let init_pos: u32 = self.pos;
-                    loop {
-                        if self.pos as usize >= input.len() {
-                            break;
-                        }
-
-                        match input[self.pos as usize] {
-                            b'.' | b'+' | b'-' | b'E' | b'e' | b'0'..=b'9' => {
-                                self.col += 1;
-                                self.pos += 1;
-                            }
-                            _ => {
-                                break;
-                            }
-                        }
-                    }
+                b' ' | b'\t' | b',' | b':' | b'[' | b']' | b'{' | b'}' if !in_string => break,
+                _ => {}
+            }
 
-                    return Some(Token {
-                        kind: TokenKind::Number,
-                        start: init_pos,
-                        end: self.pos,
-                    });
+            escaped = in_string && byte == b'\\' && !escaped;
+            match self.current_char(input) {
+                Some(ch) => {
+                    self.pos += ch.len_utf8();
+                    self.col += self.column_unit.width(ch);
                 }
-                _ => {
-                    self.col += 1;
+                None => {
                     self.pos += 1;
+                    self.col += 1;
                 }
             }
         }
     }
+
+    pub fn next_token(&mut self, input: &[u8]) -> Result<Option<Token>, JsonError> {
+        self.skip_whitespace(input);
+
+        let Some(byte) = self.peek(input) else {
+            return Ok(None);
+        };
+
+        self.last_token_col = self.col;
+        self.last_token_line = self.line;
+        self.last_token_offset = self.pos;
+
+        match byte {
+            b'{' => {
+                let init_pos = self.pos;
+
+                self.pos += 1;
+                self.col += 1;
+
+                Ok(Some(Token {
+                    kind: TokenKind::LeftBrace,
+                    start: init_pos,
+                    end: self.pos,
+                }))
+            }
+            b'}' => {
+                let init_pos = self.pos;
+
+                self.pos += 1;
+                self.col += 1;
+
+                Ok(Some(Token {
+                    kind: TokenKind::RightBrace,
+                    start: init_pos,
+                    end: self.pos,
+                }))
+            }
+            b'[' => {
+                let init_pos = self.pos;
+
+                self.pos += 1;
+                self.col += 1;
+
+                Ok(Some(Token {
+                    kind: TokenKind::LeftBracket,
+                    start: init_pos,
+                    end: self.pos,
+                }))
+            }
+            b']' => {
+                let init_pos = self.pos;
+
+                self.pos += 1;
+                self.col += 1;
+
+                Ok(Some(Token {
+                    kind: TokenKind::RightBracket,
+                    start: init_pos,
+                    end: self.pos,
+                }))
+            }
+            b':' => {
+                let init_pos = self.pos;
+
+                self.pos += 1;
+                self.col += 1;
+
+                Ok(Some(Token {
+                    kind: TokenKind::Colon,
+                    start: init_pos,
+                    end: self.pos,
+                }))
+            }
+            b',' => {
+                let init_pos = self.pos;
+
+                self.pos += 1;
+                self.col += 1;
+
+                Ok(Some(Token {
+                    kind: TokenKind::Comma,
+                    start: init_pos,
+                    end: self.pos,
+                }))
+            }
+            b'n' => self.lex_literal(input, b"null", TokenKind::Null).map(Some),
+            b't' => self.lex_literal(input, b"true", TokenKind::True).map(Some),
+            b'f' => self
+                .lex_literal(input, b"false", TokenKind::False)
+                .map(Some),
+            b'"' => self.lex_string(input).map(Some),
+            b'0'..=b'9' | b'-' => self.lex_number(input).map(Some),
+            _ => Err(self.unexpected_character(input)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -279,8 +433,8 @@ mod tests {
         let bytes = input.as_bytes();
         let mut lexer = Lexer::new();
         let mut tokens = Vec::new();
-        while (lexer.pos as usize) < bytes.len() {
-            if let Some(t) = lexer.next_token(bytes) {
+        while lexer.pos < bytes.len() {
+            if let Some(t) = lexer.next_token(bytes).expect("should tokenize") {
                 tokens.push(t);
             }
         }
@@ -288,7 +442,7 @@ mod tests {
     }
 
     fn lexeme<'a>(input: &'a str, t: &Token) -> &'a str {
-        &input[t.start as usize..t.end as usize]
+        &input[t.start..t.end]
     }
 
     fn kinds_eq(actual: &TokenKind, expected: &TokenKind) -> bool {
@@ -452,6 +606,233 @@ mod tests {
         assert_eq!(lexeme(input, &tokens[0]), "1E5");
     }
 
+    #[test]
+    fn negative_zero() {
+        let input = "-0";
+        let tokens = tokenize(input);
+        assert!(matches!(tokens[0].kind, TokenKind::Number));
+        assert_eq!(lexeme(input, &tokens[0]), "-0");
+    }
+
+    #[test]
+    fn scientific_signed_exponents() {
+        let input = "0.5E+7 1e-0";
+        let tokens = tokenize(input);
+        assert_eq!(lexeme(input, &tokens[0]), "0.5E+7");
+        assert_eq!(lexeme(input, &tokens[1]), "1e-0");
+    }
+
+    fn number_error_at(input: &str) -> (usize, usize) {
+        let mut lexer = Lexer::new();
+        loop {
+            match lexer.next_token(input.as_bytes()) {
+                Ok(Some(_)) => continue,
+                Ok(None) => panic!("expected a number error for {input:?}"),
+                Err(JsonError::InvalidNumber { span }) => return (span.line, span.col),
+                Err(err) => panic!("unexpected error {err:?} for {input:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn number_with_leading_zero() {
+        assert_eq!(number_error_at("01"), (1, 2));
+        assert_eq!(number_error_at("-00"), (1, 3));
+    }
+
+    #[test]
+    fn number_with_embedded_minus() {
+        assert_eq!(number_error_at("1-2"), (1, 2));
+    }
+
+    #[test]
+    fn number_with_double_minus() {
+        assert_eq!(number_error_at("--3"), (1, 2));
+    }
+
+    #[test]
+    fn lone_minus() {
+        assert_eq!(number_error_at("-"), (1, 2));
+        assert_eq!(number_error_at("[-]"), (1, 3));
+    }
+
+    #[test]
+    fn number_with_empty_fraction() {
+        assert_eq!(number_error_at("1."), (1, 3));
+        assert_eq!(number_error_at("1.e5"), (1, 3));
+    }
+
+    #[test]
+    fn number_with_empty_exponent() {
+        assert_eq!(number_error_at("1e"), (1, 3));
+        assert_eq!(number_error_at("1e+"), (1, 4));
+    }
+
+    #[test]
+    fn number_with_leading_dot_in_fraction() {
+        assert_eq!(number_error_at("1.2.3"), (1, 4));
+    }
+
+    #[test]
+    fn number_error_reports_line() {
+        assert_eq!(number_error_at("[\n  1,\n  2e]"), (3, 5));
+    }
+
+    #[test]
+    fn truncated_keywords() {
+        for (input, col) in [("tru", 1), ("[fals", 2), ("n", 1), ("[1, nul", 5)] {
+            let mut lexer = Lexer::new();
+            let err = loop {
+                match lexer.next_token(input.as_bytes()) {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => panic!("expected an error for {input:?}"),
+                    Err(err) => break err,
+                }
+            };
+            assert!(
+                matches!(err, JsonError::TruncatedLiteral { span } if span.line == 1 && span.col == col),
+                "{input:?} gave {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let mut lexer = Lexer::new();
+        let _ = lexer.next_token(b"[ \"abc");
+        let err = lexer.next_token(b"[ \"abc").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnterminatedString {
+                span: Span {
+                    line: 1,
+                    col: 3,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn unterminated_string_ending_in_escape() {
+        let mut lexer = Lexer::new();
+        let err = lexer.next_token(br#""abc\"#).unwrap_err();
+        assert!(matches!(err, JsonError::UnterminatedString { .. }));
+    }
+
+    fn unexpected_character_at(input: &str) -> (char, usize, usize) {
+        let mut lexer = Lexer::new();
+        loop {
+            match lexer.next_token(input.as_bytes()) {
+                Ok(Some(_)) => continue,
+                Ok(None) => panic!("expected an error for {input:?}"),
+                Err(JsonError::UnexpectedCharacter { ch, span }) => {
+                    return (ch, span.line, span.col)
+                }
+                Err(err) => panic!("unexpected error {err:?} for {input:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn unexpected_character_in_array() {
+        assert_eq!(unexpected_character_at("[1, @, 2]"), ('@', 1, 5));
+    }
+
+    #[test]
+    fn misspelled_keywords() {
+        assert_eq!(unexpected_character_at("[nope]"), ('o', 1, 3));
+        assert_eq!(unexpected_character_at("trUe"), ('U', 1, 3));
+        assert_eq!(unexpected_character_at("\n fals3"), ('3', 2, 6));
+    }
+
+    #[test]
+    fn unexpected_non_ascii_character() {
+        assert_eq!(unexpected_character_at("[é]"), ('é', 1, 2));
+    }
+
+    #[test]
+    fn control_character_in_string() {
+        assert_eq!(unexpected_character_at("\"a\tb\""), ('\t', 1, 3));
+    }
+
+    #[test]
+    fn carriage_return_is_whitespace() {
+        let tokens = tokenize("[1,\r 2]");
+        assert_eq!(tokens.len(), 5);
+    }
+
+    #[test]
+    fn crlf_advances_line_once() {
+        let positions = tokenize_with_pos("{\r\n  \"k\": 1\r\n}");
+        let line_col: Vec<(usize, usize)> = positions.iter().map(|(_, l, c)| (*l, *c)).collect();
+        assert_eq!(line_col, vec![(1, 1), (2, 3), (2, 6), (2, 8), (3, 1)]);
+    }
+
+    #[test]
+    fn lone_carriage_return_advances_line() {
+        let positions = tokenize_with_pos("[\r1,\r\r2]");
+        let line_col: Vec<(usize, usize)> = positions.iter().map(|(_, l, c)| (*l, *c)).collect();
+        assert_eq!(line_col, vec![(1, 1), (2, 1), (2, 2), (4, 1), (4, 2)]);
+    }
+
+    // Lexes `input`, skipping past each error, and returns the lexemes of the
+    // tokens read in between together with their columns.
+    fn tokenize_skipping_errors(input: &str) -> Vec<(&str, usize)> {
+        let mut lexer = Lexer::new();
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.next_token(input.as_bytes()) {
+                Ok(Some(t)) => tokens.push((lexeme(input, &t), lexer.last_token_col)),
+                Ok(None) => return tokens,
+                Err(_) => lexer.skip_invalid(input.as_bytes()),
+            }
+        }
+    }
+
+    #[test]
+    fn skip_invalid_resumes_after_bad_tokens() {
+        assert_eq!(
+            tokenize_skipping_errors("[x1, tru, 01]"),
+            [("[", 1), (",", 4), (",", 9), ("]", 13)]
+        );
+        assert_eq!(
+            tokenize_skipping_errors("[\"é\u{1}\\\"☃\" 2, \"a\nb\"]"),
+            [("[", 1), ("2", 10), (",", 11), ("]", 3)]
+        );
+    }
+
+    fn col_after_string(column_unit: ColumnUnit) -> usize {
+        let mut lexer = Lexer::with_column_unit(column_unit);
+        let input = "\"é☃😀\" 1".as_bytes();
+        let _ = lexer.next_token(input).unwrap();
+        let _ = lexer.next_token(input).unwrap();
+        lexer.last_token_col
+    }
+
+    #[test]
+    fn string_columns_in_bytes() {
+        assert_eq!(col_after_string(ColumnUnit::Bytes), 13);
+    }
+
+    #[test]
+    fn string_columns_in_chars() {
+        assert_eq!(col_after_string(ColumnUnit::Chars), 7);
+    }
+
+    #[test]
+    fn string_columns_in_utf16() {
+        assert_eq!(col_after_string(ColumnUnit::Utf16), 8);
+    }
+
+    #[test]
+    fn string_with_escaped_quote_and_backslash() {
+        let input = r#""a\"b\\" 1"#;
+        let tokens = tokenize(input);
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(lexeme(input, &tokens[0]), r#"a\"b\\"#);
+    }
+
     #[test]
     fn simple_key_value_pair() {
         let tokens = tokenize(r#"{"key": "value"}"#);
@@ -739,18 +1120,41 @@ mod tests {
         }
     }
 
-    fn tokenize_with_pos(input: &str) -> Vec<(Token, u32, u32)> {
+    fn tokenize_with_pos(input: &str) -> Vec<(Token, usize, usize)> {
         let bytes = input.as_bytes();
         let mut lexer = Lexer::new();
         let mut out = Vec::new();
-        while (lexer.pos as usize) < bytes.len() {
-            if let Some(t) = lexer.next_token(bytes) {
+        while lexer.pos < bytes.len() {
+            if let Some(t) = lexer.next_token(bytes).expect("should tokenize") {
                 out.push((t, lexer.last_token_line, lexer.last_token_col));
             }
         }
         out
     }
 
+    // Reaching offsets past `u32::MAX` for real would take 4 GiB of input, so
+    // the positions are set there directly to check that nothing holding them
+    // is narrower than `usize`. Such offsets only exist on 64-bit targets.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn offsets_past_u32_max() {
+        let past = u32::MAX as usize + 7;
+        let mut lexer = Lexer::new();
+        lexer.pos = past;
+        lexer.last_token_offset = past - 3;
+
+        let token = Token {
+            kind: TokenKind::String,
+            start: past - 2,
+            end: past - 1,
+        };
+        let span = lexer.span();
+        let last = lexer.last_token_span();
+        let offsets: [usize; 5] = [lexer.pos, token.start, token.end, span.offset, last.offset];
+        assert_eq!(offsets, [past, past - 2, past - 1, past, past - 3]);
+        assert_eq!(token.end - token.start, 1);
+    }
+
     #[test]
     fn lexer_initial_state() {
         let lexer = Lexer::new();
@@ -856,8 +1260,8 @@ mod tests {
     #[test]
     fn tracks_position_of_each_token_inline() {
         let positions = tokenize_with_pos(r#"{"k":1}"#);
-        let lines: Vec<u32> = positions.iter().map(|(_, l, _)| *l).collect();
-        let cols: Vec<u32> = positions.iter().map(|(_, _, c)| *c).collect();
+        let lines: Vec<usize> = positions.iter().map(|(_, l, _)| *l).collect();
+        let cols: Vec<usize> = positions.iter().map(|(_, _, c)| *c).collect();
         assert_eq!(lines, vec![1, 1, 1, 1, 1]);
         assert_eq!(cols, vec![1, 2, 5, 6, 7]);
     }
@@ -866,7 +1270,7 @@ mod tests {
     fn tracks_position_across_lines() {
         let input = "{\n  \"k\": 1\n}";
         let positions = tokenize_with_pos(input);
-        let line_col: Vec<(u32, u32)> = positions.iter().map(|(_, l, c)| (*l, *c)).collect();
+        let line_col: Vec<(usize, usize)> = positions.iter().map(|(_, l, c)| (*l, *c)).collect();
         assert_eq!(line_col, vec![(1, 1), (2, 3), (2, 6), (2, 8), (3, 1)]);
     }
 
@@ -889,7 +1293,7 @@ mod tests {
         let _ = lexer.next_token(bytes);
         let saved_line = lexer.last_token_line;
         let saved_col = lexer.last_token_col;
-        assert!(lexer.next_token(bytes).is_none());
+        assert!(lexer.next_token(bytes).unwrap().is_none());
         assert_eq!(lexer.last_token_line, saved_line);
         assert_eq!(lexer.last_token_col, saved_col);
     }
@@ -905,7 +1309,7 @@ mod tests {
     fn tracks_position_through_nested_structure() {
         let input = "[\n  {\n    \"k\": true\n  }\n]";
         let positions = tokenize_with_pos(input);
-        let line_col: Vec<(u32, u32)> = positions.iter().map(|(_, l, c)| (*l, *c)).collect();
+        let line_col: Vec<(usize, usize)> = positions.iter().map(|(_, l, c)| (*l, *c)).collect();
         assert_eq!(
             line_col,
             vec![
@@ -1,15 +1,50 @@
-use crate::{lexer::Lexer, parser::Parser};
-use std::{collections::HashMap, fmt};
+use crate::{
+    lexer::Lexer,
+    parser::{Parser, Recovery},
+};
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Index, IndexMut},
+};
 
+pub use crate::borrowed::JsonValueRef;
+pub use crate::error::{Diagnostic, ErrorKind, JsonError, Rendered, Span};
+pub use crate::map::Map;
+pub use crate::number::Number;
+pub use crate::typed::{FromJson, FromJsonError, FromJsonErrorKind, ToJson};
+#[cfg(feature = "serde")]
+pub use crate::{de::from_str, ser::to_string};
+#[cfg(feature = "derive")]
+pub use rsjson_derive::{FromJson, ToJson};
+
+#[macro_use]
+mod macros;
+
+mod borrowed;
+mod convert;
+#[cfg(feature = "serde")]
+mod de;
+mod error;
 mod lexer;
+pub mod map;
+mod number;
 mod parser;
+#[cfg(feature = "serde")]
+mod ser;
+mod typed;
 
-#[derive(Debug, PartialEq)]
+/// A parsed JSON document.
+///
+/// Values are `Eq` and `Hash`, so they can be used in sets and as map keys:
+/// objects ignore the order of their members and numbers compare as described
+/// on [`Number`]. [`Ord`] is a total order across variants.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum JsonValue {
-    Object(HashMap<String, JsonValue>),
+    Object(Map),
     Array(Vec<JsonValue>),
     String(String),
-    Number(f64),
+    Number(Number),
     Boolean(bool),
     Null,
 }
@@ -20,6 +55,91 @@ impl fmt::Display for JsonValue {
     }
 }
 
+impl PartialOrd for JsonValue {
+    fn partial_cmp(&self, other: &JsonValue) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders values of different kinds as null < boolean < number < string <
+/// array < object. Within a kind, booleans have `false` first, numbers are
+/// ordered as [`Number`], strings by their bytes, arrays lexicographically and
+/// objects as their members sorted by key.
+impl Ord for JsonValue {
+    fn cmp(&self, other: &JsonValue) -> Ordering {
+        match (self, other) {
+            (JsonValue::Boolean(a), JsonValue::Boolean(b)) => a.cmp(b),
+            (JsonValue::Number(a), JsonValue::Number(b)) => a.cmp(b),
+            (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+            (JsonValue::Array(a), JsonValue::Array(b)) => a.cmp(b),
+            (JsonValue::Object(a), JsonValue::Object(b)) => a.cmp(b),
+            _ => self.kind().cmp(&other.kind()),
+        }
+    }
+}
+
+// Returned by the `Index` impls for members and elements that are missing.
+static NULL: JsonValue = JsonValue::Null;
+
+/// Looks up an object member, giving `null` when the member is missing or the
+/// value is not an object, so lookups can be chained as `value["a"]["b"]`.
+impl Index<&str> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, key: &str) -> &JsonValue {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// Looks up an array element, giving `null` when the index is out of bounds
+/// or the value is not an array.
+impl Index<usize> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, index: usize) -> &JsonValue {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+/// Gets an object member to assign to, inserting `null` if it is missing. A
+/// `null` value is first turned into an empty object, so nested members can
+/// be created with `value["a"]["b"] = ...`.
+///
+/// # Panics
+///
+/// Panics if the value is neither an object nor `null`.
+impl IndexMut<&str> for JsonValue {
+    fn index_mut(&mut self, key: &str) -> &mut JsonValue {
+        if self.is_null() {
+            *self = JsonValue::Object(Map::new());
+        }
+
+        match self {
+            JsonValue::Object(map) => map.entry(key.to_string()).or_insert(JsonValue::Null),
+            other => panic!("cannot index {} with a key", other.describe()),
+        }
+    }
+}
+
+/// Gets an array element to assign to.
+///
+/// # Panics
+///
+/// Panics if the value is not an array or the index is out of bounds.
+impl IndexMut<usize> for JsonValue {
+    fn index_mut(&mut self, index: usize) -> &mut JsonValue {
+        match self {
+            JsonValue::Array(arr) => {
+                let len = arr.len();
+                arr.get_mut(index).unwrap_or_else(|| {
+                    panic!("index {index} is out of bounds for an array of length {len}")
+                })
+            }
+            other => panic!("cannot index {} with a number", other.describe()),
+        }
+    }
+}
+
 impl JsonValue {
     pub fn as_str(&self) -> Option<&str> {
         match self {
@@ -30,7 +150,21 @@ impl JsonValue {
 
     pub fn as_f64(&self) -> Option<f64> {
         match self {
-            JsonValue::Number(num) => Some(*num),
+            JsonValue::Number(num) => Some(num.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Number(num) => num.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(num) => num.as_u64(),
             _ => None,
         }
     }
@@ -42,6 +176,129 @@ impl JsonValue {
         }
     }
 
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            JsonValue::Number(num) => Some(num),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            JsonValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            JsonValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            JsonValue::String(str) => Some(str),
+            _ => None,
+        }
+    }
+
+    pub fn into_number(self) -> Option<Number> {
+        match self {
+            JsonValue::Number(num) => Some(num),
+            _ => None,
+        }
+    }
+
+    pub fn into_array(self) -> Option<Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn into_object(self) -> Option<Map> {
+        match self {
+            JsonValue::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> JsonKind {
+        match self {
+            JsonValue::Null => JsonKind::Null,
+            JsonValue::Boolean(_) => JsonKind::Boolean,
+            JsonValue::Number(_) => JsonKind::Number,
+            JsonValue::String(_) => JsonKind::String,
+            JsonValue::Array(_) => JsonKind::Array,
+            JsonValue::Object(_) => JsonKind::Object,
+        }
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, JsonValue::Boolean(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, JsonValue::Number(_))
+    }
+
+    /// Whether the value is a number that fits in an `i64`.
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Whether the value is a number that fits in a `u64`.
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Whether the value is a number stored as an `f64`, see [`Number::is_f64`].
+    pub fn is_f64(&self) -> bool {
+        self.as_number().is_some_and(Number::is_f64)
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, JsonValue::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JsonValue::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, JsonValue::Object(_))
+    }
+
+    /// The number of elements of an array or members of an object. Other
+    /// values have no elements and a length of 0.
+    pub fn len(&self) -> usize {
+        match self {
+            JsonValue::Array(arr) => arr.len(),
+            JsonValue::Object(map) => map.len(),
+            _ => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn get(&self, key: &str) -> Option<&JsonValue> {
         match self {
             JsonValue::Object(map) => map.get(key),
@@ -60,6 +317,156 @@ impl JsonValue {
         matches!(self, JsonValue::Null)
     }
 
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue> {
+        match self {
+            JsonValue::Object(map) => map.get_mut(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.get_mut(index),
+            _ => None,
+        }
+    }
+
+    /// Inserts a member into an object and returns the value it replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not an object.
+    pub fn insert(&mut self, key: impl Into<String>, value: JsonValue) -> Option<JsonValue> {
+        match self {
+            JsonValue::Object(map) => map.insert(key.into(), value),
+            other => panic!("cannot insert a member into {}", other.describe()),
+        }
+    }
+
+    /// Removes a member from an object and returns its value. Values other
+    /// than objects have no members and return `None`.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+        match self {
+            JsonValue::Object(map) => map.remove(key),
+            _ => None,
+        }
+    }
+
+    /// Appends an element to an array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not an array.
+    pub fn push(&mut self, value: JsonValue) {
+        match self {
+            JsonValue::Array(arr) => arr.push(value),
+            other => panic!("cannot push an element onto {}", other.describe()),
+        }
+    }
+
+    /// Takes the value out, leaving `null` in its place.
+    pub fn take(&mut self) -> JsonValue {
+        std::mem::replace(self, JsonValue::Null)
+    }
+
+    /// Drops the value without recursing into its arrays and objects.
+    ///
+    /// Dropping a value normally recurses once per level of nesting. That is
+    /// safe for anything parsed, which is never deeper than
+    /// [`ParseOptions::DEPTH_CEILING`], but a value built by hand can nest
+    /// deeper than the stack allows.
+    pub fn drop_iteratively(self) {
+        let mut stack = vec![self];
+        // Each value's children are moved onto the stack before the value is
+        // dropped, so no drop has more than one level to go through.
+        while let Some(value) = stack.pop() {
+            match value {
+                JsonValue::Array(values) => stack.extend(values),
+                JsonValue::Object(map) => stack.extend(map.into_iter().map(|(_, value)| value)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Looks up a value by a JSON Pointer (RFC 6901) such as `/users/0/name`.
+    /// The empty pointer refers to the whole value.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        let mut value = self;
+        for token in Self::pointer_tokens(pointer)? {
+            value = match value {
+                JsonValue::Object(map) => map.get(&token)?,
+                JsonValue::Array(arr) => arr.get(Self::pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Like [`pointer`](JsonValue::pointer), returning a mutable reference so
+    /// a nested value can be changed in place.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue> {
+        let mut value = self;
+        for token in Self::pointer_tokens(pointer)? {
+            value = match value {
+                JsonValue::Object(map) => map.get_mut(&token)?,
+                JsonValue::Array(arr) => arr.get_mut(Self::pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    // Splits a pointer into its reference tokens, undoing the `~1` and `~0`
+    // escapes for '/' and '~'. A non-empty pointer must start with '/'.
+    fn pointer_tokens(pointer: &str) -> Option<impl Iterator<Item = String> + '_> {
+        let rest = match pointer {
+            "" => None,
+            _ => Some(pointer.strip_prefix('/')?),
+        };
+
+        let tokens = rest.into_iter().flat_map(|rest| rest.split('/'));
+        Some(tokens.map(|token| token.replace("~1", "/").replace("~0", "~")))
+    }
+
+    // Array indices in a pointer are decimal without leading zeros.
+    fn pointer_index(token: &str) -> Option<usize> {
+        if (token.len() > 1 && token.starts_with('0')) || !token.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        token.parse().ok()
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            JsonValue::Object(_) => "an object",
+            JsonValue::Array(_) => "an array",
+            JsonValue::String(_) => "a string",
+            JsonValue::Number(_) => "a number",
+            JsonValue::Boolean(_) => "a boolean",
+            JsonValue::Null => "null",
+        }
+    }
+
+    // Writes `str` as a quoted JSON string.
+    pub(crate) fn write_escaped(f: &mut impl fmt::Write, str: &str) -> fmt::Result {
+        write!(f, "\"")?;
+        for ch in str.chars() {
+            match ch {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                '\u{8}' => write!(f, "\\b")?,
+                '\u{c}' => write!(f, "\\f")?,
+                ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+                ch => write!(f, "{ch}")?,
+            }
+        }
+        write!(f, "\"")
+    }
+
     fn pretty_print(
         f: &mut fmt::Formatter<'_>,
         json_value: &JsonValue,
@@ -69,7 +476,7 @@ impl JsonValue {
         let next_indent = "    ".repeat(indent + 1);
 
         match json_value {
-            JsonValue::String(str) => write!(f, "\"{}\"", str),
+            JsonValue::String(str) => Self::write_escaped(f, str),
             JsonValue::Number(num) => write!(f, "{}", num),
             JsonValue::Boolean(val) => write!(f, "{}", val),
             JsonValue::Null => write!(f, "null"),
@@ -78,14 +485,14 @@ impl JsonValue {
                     return write!(f, "[]");
                 }
 
-                write!(f, "[\n")?;
+                writeln!(f, "[")?;
                 for (i, val) in array.iter().enumerate() {
                     write!(f, "{next_indent}")?;
                     Self::pretty_print(f, val, indent + 1)?;
                     if i != array.len() - 1 {
                         write!(f, ",")?;
                     }
-                    write!(f, "\n")?;
+                    writeln!(f)?;
                 }
                 write!(f, "{cur_indent}")?;
                 write!(f, "]")?;
@@ -96,15 +503,16 @@ impl JsonValue {
                     return write!(f, "{{}}");
                 }
 
-                write!(f, "{{\n")?;
+                writeln!(f, "{{")?;
                 for (i, record) in object.iter().enumerate() {
                     write!(f, "{next_indent}")?;
-                    write!(f, "\"{}\": ", record.0)?;
+                    Self::write_escaped(f, record.0)?;
+                    write!(f, ": ")?;
                     Self::pretty_print(f, record.1, indent + 1)?;
                     if i != object.len() - 1 {
                         write!(f, ",")?;
                     }
-                    write!(f, "\n")?;
+                    writeln!(f)?;
                 }
                 write!(f, "{cur_indent}")?;
                 write!(f, "}}")?;
@@ -114,54 +522,229 @@ impl JsonValue {
     }
 }
 
-#[derive(Debug)]
-pub enum JsonError {
-    UnexpectedToken { line: usize, col: usize },
-    UnexpectedEof,
-    InvalidNumber(std::num::ParseFloatError),
-    InvalidUtf8(std::str::Utf8Error),
-    DuplicateKey(String),
+/// The kind of a [`JsonValue`], as returned by [`JsonValue::kind`]. Kinds are
+/// ordered as values of different kinds are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JsonKind {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
 }
 
-impl std::error::Error for JsonError {}
+impl JsonKind {
+    /// The name of the kind, such as `"array"`, for messages like
+    /// "expected array, got object".
+    pub fn name(self) -> &'static str {
+        match self {
+            JsonKind::Null => "null",
+            JsonKind::Boolean => "boolean",
+            JsonKind::Number => "number",
+            JsonKind::String => "string",
+            JsonKind::Array => "array",
+            JsonKind::Object => "object",
+        }
+    }
+}
 
-impl fmt::Display for JsonError {
+impl fmt::Display for JsonKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The unit used to count the `col` reported in a [`JsonError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnUnit {
+    /// UTF-8 bytes.
+    Bytes,
+    /// Unicode scalar values, so every character counts as one column.
+    #[default]
+    Chars,
+    /// UTF-16 code units, as used by most editors and language servers.
+    Utf16,
+}
+
+impl ColumnUnit {
+    pub(crate) fn width(self, ch: char) -> usize {
         match self {
-            JsonError::UnexpectedToken { line, col } => {
-                write!(f, "unexpeted token at line {line}, col {col}")
-            }
-            JsonError::UnexpectedEof => write!(f, "unexpected end of input"),
-            JsonError::InvalidNumber(err) => write!(f, "invalid number : {err}"),
-            JsonError::DuplicateKey(key) => write!(f, "Duplicate key : {key}"),
-            JsonError::InvalidUtf8(key) => write!(f, "Invalid utf8 : {key}"),
+            ColumnUnit::Bytes => ch.len_utf8(),
+            ColumnUnit::Chars => 1,
+            ColumnUnit::Utf16 => ch.len_utf16(),
+        }
+    }
+
+    pub(crate) fn measure(self, text: &str) -> usize {
+        match self {
+            ColumnUnit::Bytes => text.len(),
+            ColumnUnit::Chars => text.chars().count(),
+            ColumnUnit::Utf16 => text.encode_utf16().count(),
+        }
+    }
+}
+
+/// How [`ParseOptions::duplicate_keys`] treats a key that appears more than
+/// once in the same object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Fail with [`JsonError::DuplicateKey`] at the repeated key.
+    #[default]
+    Error,
+    /// Keep the first value and ignore later ones.
+    FirstWins,
+    /// Keep the last value. The key stays where it first appeared.
+    LastWins,
+    /// Gather every value of a repeated key into an array, in input order.
+    /// Keys that appear once keep their value as it is.
+    Collect,
+}
+
+/// Options for [`parse_with_options`] and [`parse_bytes_with_options`].
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    pub column_unit: ColumnUnit,
+    // Kept private so that it can only be set through `with_max_depth`, which
+    // checks it against the ceiling.
+    max_depth: usize,
+    /// What to do with a key repeated within one object, an error by default.
+    pub duplicate_keys: DuplicateKeys,
+    /// Keep the literal text of every number so that it prints back exactly
+    /// as it was read, see [`Number`]. Off by default.
+    pub raw_numbers: bool,
+}
+
+impl ParseOptions {
+    /// The highest `max_depth` that can be set.
+    ///
+    /// Parsing does not recurse, but dropping, printing, comparing, hashing,
+    /// cloning and converting a value recurse once per level. Values this deep
+    /// can do all of those on a 2 MiB stack, the default for spawned threads,
+    /// even in debug builds.
+    pub const DEPTH_CEILING: usize = 512;
+
+    /// The deepest nesting of arrays and objects accepted, 128 by default.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Sets [`max_depth`](ParseOptions::max_depth), failing when it is above
+    /// [`ParseOptions::DEPTH_CEILING`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Result<ParseOptions, MaxDepthError> {
+        if max_depth > Self::DEPTH_CEILING {
+            return Err(MaxDepthError { max_depth });
         }
+        self.max_depth = max_depth;
+        Ok(self)
     }
 }
 
-impl From<std::num::ParseFloatError> for JsonError {
-    fn from(err: std::num::ParseFloatError) -> Self {
-        JsonError::InvalidNumber(err)
+/// The error of [`ParseOptions::with_max_depth`] for a depth above
+/// [`ParseOptions::DEPTH_CEILING`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxDepthError {
+    pub max_depth: usize,
+}
+
+impl std::error::Error for MaxDepthError {}
+
+impl fmt::Display for MaxDepthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max_depth of {} is above the ceiling of {}",
+            self.max_depth,
+            ParseOptions::DEPTH_CEILING
+        )
     }
 }
 
-impl From<std::str::Utf8Error> for JsonError {
-    fn from(err: std::str::Utf8Error) -> Self {
-        JsonError::InvalidUtf8(err)
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            column_unit: ColumnUnit::default(),
+            max_depth: 128,
+            duplicate_keys: DuplicateKeys::default(),
+            raw_numbers: false,
+        }
     }
 }
 
 pub fn parse(input: &str) -> Result<JsonValue, JsonError> {
-    let lexer = Lexer::new();
-    let parser = Parser::new(input.as_bytes(), lexer);
+    parse_bytes(input.as_bytes())
+}
+
+/// Parses JSON from raw bytes, such as a buffer read from a socket or file.
+/// The input does not have to be valid UTF-8 up front: string contents are
+/// validated while lexing and any other non-ASCII byte is rejected, with
+/// [`JsonError::InvalidUtf8`] reporting where the bad sequence starts.
+pub fn parse_bytes(input: &[u8]) -> Result<JsonValue, JsonError> {
+    parse_bytes_with_options(input, &ParseOptions::default())
+}
+
+/// Like [`parse`], configured by `options`.
+pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<JsonValue, JsonError> {
+    parse_bytes_with_options(input.as_bytes(), options)
+}
+
+/// Like [`parse_bytes`], configured by `options`.
+pub fn parse_bytes_with_options(
+    input: &[u8],
+    options: &ParseOptions,
+) -> Result<JsonValue, JsonError> {
+    let lexer = Lexer::with_column_unit(options.column_unit);
+    let parser = Parser::new(input, lexer, options);
 
     parser.parse()
 }
 
+/// Parses `input` into a [`JsonValueRef`] that borrows its strings and keys
+/// from `input` instead of copying them. Only strings with escape sequences
+/// are decoded into new allocations.
+pub fn parse_borrowed(input: &str) -> Result<JsonValueRef<'_>, JsonError> {
+    parse_borrowed_with_options(input, &ParseOptions::default())
+}
+
+/// Like [`parse_borrowed`], configured by `options`.
+pub fn parse_borrowed_with_options<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<JsonValueRef<'a>, JsonError> {
+    let lexer = Lexer::with_column_unit(options.column_unit);
+    let parser = Parser::new(input.as_bytes(), lexer, options);
+
+    parser.parse()
+}
+
+/// Parses the JSON value at the start of `input` and returns it together with
+/// the number of bytes it occupies. Unlike [`parse`], content after the value
+/// is left unread, so the rest of the input can be handled by the caller.
+pub fn parse_prefix(input: &str) -> Result<(JsonValue, usize), JsonError> {
+    let lexer = Lexer::new();
+    let parser = Parser::new(input.as_bytes(), lexer, &ParseOptions::default());
+
+    parser.parse_prefix()
+}
+
+/// Parses `input` without stopping at the first error, for editors and other
+/// tools that show every problem at once. After an error parsing resumes at
+/// the next `,`, `]` or `}`, missing values are filled in with `null`, and
+/// the value is `None` only when the input holds no value at all.
+pub fn parse_with_recovery(input: &str) -> (Option<JsonValue>, Vec<Diagnostic>) {
+    let lexer = Lexer::new();
+    let parser = Parser::new(input.as_bytes(), lexer, &ParseOptions::default());
+
+    Recovery::new(parser).parse()
+}
+
 #[cfg(test)]
 mod parser_tests {
 
-    use crate::parse;
+    use crate::{
+        parse, parse_bytes, parse_prefix, parse_with_options, parse_with_recovery, ColumnUnit,
+        DuplicateKeys, ErrorKind, JsonError, JsonKind, JsonValue, Map, Number, ParseOptions, Span,
+    };
 
     #[test]
     fn parse_array() {
@@ -359,58 +942,1350 @@ mod parser_tests {
     }
 
     #[test]
-    fn test_display_null() {
-        let result = parse("null").unwrap();
-        assert_eq!(result.to_string(), "null");
+    fn parse_string_with_simple_escapes() {
+        let result = parse(r#""a\"b\\c\/d\be\ff\ng\rh\ti""#).expect("should parse escapes");
+        assert_eq!(result.as_str(), Some("a\"b\\c/d\u{8}e\u{c}f\ng\rh\ti"));
     }
 
     #[test]
-    fn test_display_boolean_true() {
-        let result = parse("true").unwrap();
-        assert_eq!(result.to_string(), "true");
+    fn parse_string_with_unicode_escape() {
+        let result = parse(r#""caf\u00e9 \u2603""#).expect("should parse unicode escape");
+        assert_eq!(result.as_str(), Some("café ☃"));
     }
 
     #[test]
-    fn test_display_boolean_false() {
-        let result = parse("false").unwrap();
-        assert_eq!(result.to_string(), "false");
+    fn parse_string_with_surrogate_pair() {
+        let result = parse(r#""\ud83d\ude00""#).expect("should parse surrogate pair");
+        assert_eq!(result.as_str(), Some("😀"));
     }
 
     #[test]
-    fn test_display_number() {
-        let result = parse("123").unwrap();
-        assert_eq!(result.to_string(), "123");
+    fn parse_escaped_object_key_and_value() {
+        let result = parse(r#"{"a\nb":["x\ty"]}"#).expect("should parse escaped key");
+        assert_eq!(
+            result
+                .get("a\nb")
+                .and_then(|v| v.get_index(0))
+                .and_then(|v| v.as_str()),
+            Some("x\ty")
+        );
     }
 
     #[test]
-    fn test_display_string() {
-        let result = parse(r#""hello""#).unwrap();
-        assert_eq!(result.to_string(), "\"hello\"");
+    fn parse_invalid_escape() {
+        let err = parse(r#"["ok", "a\qb"]"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidEscape {
+                span: Span {
+                    line: 1,
+                    col: 10,
+                    ..
+                }
+            }
+        ));
     }
 
     #[test]
-    fn test_display_empty_array() {
-        let result = parse("[]").unwrap();
-        let output = result.to_string();
-        assert!(output.contains("["));
-        assert!(output.contains("]"));
+    fn parse_short_unicode_escape() {
+        let err = parse(r#""\u12""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidEscape {
+                span: Span {
+                    line: 1,
+                    col: 2,
+                    ..
+                }
+            }
+        ));
     }
 
     #[test]
-    fn test_display_empty_object() {
-        let result = parse("{}").unwrap();
-        let output = result.to_string();
-        assert!(output.contains("{"));
-        assert!(output.contains("}"));
+    fn parse_lone_high_surrogate() {
+        let err = parse(r#""ab\ud83d""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::LoneSurrogate {
+                span: Span {
+                    line: 1,
+                    col: 4,
+                    ..
+                }
+            }
+        ));
     }
 
     #[test]
-    fn test_display_simple_array() {
-        let result = parse("[1,2,3]").unwrap();
-        let output = result.to_string();
-        assert!(output.contains("1"));
-        assert!(output.contains("2"));
-        assert!(output.contains("3"));
+    fn parse_lone_low_surrogate() {
+        let err = parse(r#""\ude00\ud83d""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::LoneSurrogate {
+                span: Span {
+                    line: 1,
+                    col: 2,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_high_surrogate_followed_by_non_surrogate() {
+        let err = parse(r#""\ud83d\u0041""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::LoneSurrogate {
+                span: Span {
+                    line: 1,
+                    col: 2,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_display_escapes_strings() {
+        let result = parse(r#"{"k\"":"a\nb\u0001"}"#).unwrap();
+        let output = result.to_string();
+        assert!(output.contains(r#""k\"": "a\nb\u0001""#));
+        assert_eq!(parse(&output).unwrap(), result);
+    }
+
+    #[test]
+    fn parse_number_grammar() {
+        assert_eq!(parse("-0").unwrap().as_f64(), Some(-0.0));
+        assert_eq!(parse("1.5e3").unwrap().as_f64(), Some(1500.0));
+        assert_eq!(parse("2E-2").unwrap().as_f64(), Some(0.02));
+    }
+
+    #[test]
+    fn parse_invalid_numbers() {
+        for input in ["01", "1.", "--3", "1e", "-", "[1-2]", r#"{"a":1.2.3}"#] {
+            let err = parse(input).unwrap_err();
+            assert!(
+                matches!(err, JsonError::InvalidNumber { .. }),
+                "{input:?} gave {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_out_of_range_numbers() {
+        let err = parse("[1, 1e400]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidNumber {
+                span: Span { col: 5, .. }
+            }
+        ));
+
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..ParseOptions::default()
+        };
+        let err = parse_with_options("-1e400", &options).unwrap_err();
+        assert!(matches!(err, JsonError::InvalidNumber { .. }));
+    }
+
+    #[test]
+    fn parse_invalid_number_position() {
+        let err = parse("[10, 2.x]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidNumber {
+                span: Span {
+                    line: 1,
+                    col: 8,
+                    ..
+                }
+            }
+        ));
+    }
+
+    const TRUNCATION_CORPUS: &[&str] = &[
+        r#"{"name":"prudhvi","age":25,"tags":["a","b"],"ok":true,"none":null,"off":false}"#,
+        r#"[1, -2.5e+10, "esc\"aped\\", "\u00e9\ud83d\ude00", [[]], {}]"#,
+        "{\n  \"nested\": {\"deep\": [true, false, null]}\n}",
+        r#""caf\u00e9 ☃""#,
+        "-0.0e-0",
+    ];
+
+    #[test]
+    fn parse_truncated_inputs_never_panic() {
+        for document in TRUNCATION_CORPUS {
+            assert!(parse(document).is_ok(), "{document:?} should parse");
+
+            for end in (0..document.len()).filter(|&end| document.is_char_boundary(end)) {
+                let prefix = &document[..end];
+                let result = std::panic::catch_unwind(|| parse(prefix));
+                assert!(result.is_ok(), "parse panicked on {prefix:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_truncated_literal() {
+        let err = parse("tru").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::TruncatedLiteral {
+                span: Span {
+                    line: 1,
+                    col: 1,
+                    ..
+                }
+            }
+        ));
+        let err = parse("[n").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::TruncatedLiteral {
+                span: Span {
+                    line: 1,
+                    col: 2,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_unterminated_string() {
+        let err = parse(r#""abc"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnterminatedString {
+                span: Span {
+                    line: 1,
+                    col: 1,
+                    ..
+                }
+            }
+        ));
+        let err = parse(r#"{"a": "b\""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnterminatedString {
+                span: Span {
+                    line: 1,
+                    col: 7,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_unexpected_characters() {
+        let err = parse("[1, @, 2]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedCharacter {
+                ch: '@',
+                span: Span {
+                    line: 1,
+                    col: 5,
+                    ..
+                }
+            }
+        ));
+        let err = parse(r#"{"a": xyz}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedCharacter {
+                ch: 'x',
+                span: Span {
+                    line: 1,
+                    col: 7,
+                    ..
+                }
+            }
+        ));
+        for input in ["[nope]", "+1", ".5", "'a'", "[1, 2, x]"] {
+            assert!(parse(input).is_err(), "{input:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn parse_trailing_content() {
+        let err = parse(r#"{"a":1} garbage"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::TrailingCharacters {
+                span: Span {
+                    line: 1,
+                    col: 9,
+                    ..
+                }
+            }
+        ));
+        let err = parse("[1][2]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::TrailingCharacters {
+                span: Span {
+                    line: 1,
+                    col: 4,
+                    ..
+                }
+            }
+        ));
+        let err = parse("1 2").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::TrailingCharacters {
+                span: Span {
+                    line: 1,
+                    col: 3,
+                    ..
+                }
+            }
+        ));
+        let err = parse("true\n\n  }").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::TrailingCharacters {
+                span: Span {
+                    line: 3,
+                    col: 3,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_trailing_whitespace() {
+        let result = parse("  [1]\r\n\t ").expect("trailing whitespace is allowed");
+        assert_eq!(result.get_index(0).and_then(|v| v.as_f64()), Some(1.0));
+    }
+
+    #[test]
+    fn parse_prefix_reports_consumed_bytes() {
+        let (value, consumed) = parse_prefix(r#"  {"a":1} {"b":2}"#).unwrap();
+        assert_eq!(value.get("a").and_then(|v| v.as_f64()), Some(1.0));
+        assert_eq!(consumed, 9);
+
+        let (value, consumed) = parse_prefix("[1][2]").unwrap();
+        assert_eq!(value.get_index(0).and_then(|v| v.as_f64()), Some(1.0));
+        assert_eq!(consumed, 3);
+
+        let (value, consumed) = parse_prefix("12 garbage").unwrap();
+        assert_eq!(value.as_f64(), Some(12.0));
+        assert_eq!(consumed, 2);
+    }
+
+    #[test]
+    fn parse_prefix_of_stream() {
+        let mut input = "1 \"two\" [3]";
+        let mut values = Vec::new();
+        while !input.trim().is_empty() {
+            let (value, consumed) = parse_prefix(input).unwrap();
+            values.push(value);
+            input = &input[consumed..];
+        }
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[1].as_str(), Some("two"));
+    }
+
+    fn expected_at(input: &str) -> (&'static str, usize, usize) {
+        match parse(input) {
+            Err(JsonError::UnexpectedToken { expected, span, .. }) => {
+                (expected, span.line, span.col)
+            }
+            other => panic!("expected an unexpected token error for {input:?}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_array_missing_comma() {
+        assert_eq!(expected_at("[1 2]"), ("',' or ']'", 1, 4));
+    }
+
+    #[test]
+    fn parse_array_leading_comma() {
+        assert_eq!(expected_at("[,1]"), ("a value or ']'", 1, 2));
+    }
+
+    #[test]
+    fn parse_array_trailing_comma() {
+        assert_eq!(expected_at("[1,]"), ("a value", 1, 4));
+    }
+
+    #[test]
+    fn parse_array_with_colon() {
+        assert_eq!(expected_at("[1: 2]"), ("',' or ']'", 1, 3));
+    }
+
+    #[test]
+    fn parse_object_missing_comma() {
+        assert_eq!(expected_at(r#"{"a":1 "b":2}"#), ("',' or '}'", 1, 8));
+    }
+
+    #[test]
+    fn parse_object_missing_value() {
+        assert_eq!(expected_at(r#"{"a":,"b":1}"#), ("a value", 1, 6));
+    }
+
+    #[test]
+    fn parse_object_missing_colon() {
+        assert_eq!(expected_at(r#"{"a" 1}"#), ("':'", 1, 6));
+    }
+
+    #[test]
+    fn parse_object_trailing_comma() {
+        assert_eq!(expected_at(r#"{"a":1,}"#), ("a string key", 1, 8));
+    }
+
+    #[test]
+    fn parse_object_non_string_key() {
+        assert_eq!(expected_at("{1:2}"), ("a string key or '}'", 1, 2));
+        assert_eq!(expected_at(r#"{"a":1,,"b":2}"#), ("a string key", 1, 8));
+    }
+
+    #[test]
+    fn parse_mismatched_closers() {
+        assert_eq!(expected_at("[1}"), ("',' or ']'", 1, 3));
+        assert_eq!(expected_at(r#"{"a":1]"#), ("',' or '}'", 1, 7));
+    }
+
+    #[test]
+    fn parse_unexpected_token_message() {
+        let err = parse("[1 2]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected ',' or ']', found a number at line 1, col 4"
+        );
+    }
+
+    #[test]
+    fn parse_error_names_found_token() {
+        let err = parse(r#"{"a" 1}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedToken {
+                found: "a number",
+                expected: "':'",
+                ..
+            }
+        ));
+        let err = parse(r#"{"a":1:"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedToken {
+                found: "':'",
+                expected: "',' or '}'",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_object_with_non_string_key_mid_input() {
+        let err = parse("{\"a\": 1,\n  true: 2}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected a string key, found 'true' at line 2, col 3"
+        );
+        assert_eq!(
+            err.span(),
+            Some(Span {
+                offset: 11,
+                line: 2,
+                col: 3
+            })
+        );
+    }
+
+    #[test]
+    fn parse_unexpected_eof_position() {
+        let err = parse("[1,\n  ").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedEof {
+                expected: "a value",
+                span: Span {
+                    offset: 6,
+                    line: 2,
+                    col: 3
+                }
+            }
+        ));
+        let err = parse("").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected end of input, expected a value at line 1, col 1"
+        );
+        let err = parse(r#"{"a""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedEof {
+                expected: "':'",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_duplicate_key_position() {
+        let err = parse("{\"a\": 1,\n \"a\": 2}").unwrap_err();
+        match err {
+            JsonError::DuplicateKey { key, span } => {
+                assert_eq!(key, "a");
+                assert_eq!(
+                    span,
+                    Span {
+                        offset: 10,
+                        line: 2,
+                        col: 2
+                    }
+                );
+            }
+            other => panic!("expected a duplicate key error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_error_offsets() {
+        let err = parse("[\"é\", 0x]").unwrap_err();
+        assert_eq!(err.span().unwrap().offset, 8);
+        assert_eq!(err.span().unwrap().col, 8);
+        let err = parse(r#"["ab\q"]"#).unwrap_err();
+        assert_eq!(err.span().unwrap().offset, 4);
+    }
+
+    #[test]
+    fn parse_bytes_valid_input() {
+        let result = parse_bytes("{\"city\": \"Zürich ☃ 😀\"}".as_bytes()).unwrap();
+        assert_eq!(
+            result.get("city").and_then(|v| v.as_str()),
+            Some("Zürich ☃ 😀")
+        );
+    }
+
+    #[test]
+    fn parse_bytes_invalid_utf8_in_string() {
+        let err = parse_bytes(b"[\"ok\",\n \"a\xffb\"]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidUtf8 {
+                span: Span {
+                    offset: 10,
+                    line: 2,
+                    col: 4
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_bytes_truncated_utf8_sequence() {
+        let err = parse_bytes(b"\"\xe2\x98\"").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidUtf8 {
+                span: Span {
+                    offset: 1,
+                    line: 1,
+                    col: 2
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_bytes_overlong_and_surrogate_encodings() {
+        for input in [&b"\"\xc0\xaf\""[..], b"\"\xed\xa0\x80\"", b"\"\x80\""] {
+            let err = parse_bytes(input).unwrap_err();
+            assert!(
+                matches!(err, JsonError::InvalidUtf8 { span } if span.offset == 1),
+                "{input:?} gave {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_bytes_invalid_utf8_outside_string() {
+        let err = parse_bytes(b"[1, \xff]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidUtf8 {
+                span: Span {
+                    offset: 4,
+                    line: 1,
+                    col: 5
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_escape_after_multibyte_text() {
+        let result = parse(r#""é\n☃\"""#).unwrap();
+        assert_eq!(result.as_str(), Some("é\n☃\""));
+    }
+
+    #[test]
+    fn parse_crlf_document() {
+        let input = "{\r\n  \"a\": 1,\r\n  \"b\": [true]\r\n}\r\n";
+        let result = parse(input).expect("should parse crlf document");
+        assert_eq!(result.get("a").and_then(|v| v.as_f64()), Some(1.0));
+
+        let err = parse("{\r\n  \"a\": 1,\r\n  \"b\" true\r\n}").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedToken {
+                span: Span {
+                    line: 3,
+                    col: 7,
+                    ..
+                },
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_lone_carriage_return_line_breaks() {
+        let err = parse("[1,\r2,\r@]").unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::UnexpectedCharacter {
+                ch: '@',
+                span: Span {
+                    line: 3,
+                    col: 1,
+                    ..
+                }
+            }
+        ));
+    }
+
+    fn unexpected_col(input: &str, column_unit: ColumnUnit) -> usize {
+        let options = ParseOptions {
+            column_unit,
+            ..ParseOptions::default()
+        };
+        match parse_with_options(input, &options) {
+            Err(JsonError::UnexpectedToken { span, .. }) => span.col,
+            other => panic!("expected an unexpected token error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_error_columns_after_non_ascii() {
+        let input = r#"["né", "😀" 1]"#;
+        assert_eq!(unexpected_col(input, ColumnUnit::Bytes), 16);
+        assert_eq!(unexpected_col(input, ColumnUnit::Chars), 12);
+        assert_eq!(unexpected_col(input, ColumnUnit::Utf16), 13);
+        assert_eq!(unexpected_col(input, ColumnUnit::default()), 12);
+    }
+
+    #[test]
+    fn parse_escape_error_columns_after_non_ascii() {
+        let options = ParseOptions {
+            column_unit: ColumnUnit::Utf16,
+            ..ParseOptions::default()
+        };
+        let err = parse_with_options(r#""😀é\x""#, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidEscape {
+                span: Span {
+                    line: 1,
+                    col: 5,
+                    ..
+                }
+            }
+        ));
+
+        let err = parse(r#""😀é\x""#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::InvalidEscape {
+                span: Span {
+                    line: 1,
+                    col: 4,
+                    ..
+                }
+            }
+        ));
+    }
+
+    fn with_max_depth(max_depth: usize) -> ParseOptions {
+        ParseOptions::default().with_max_depth(max_depth).unwrap()
+    }
+
+    #[test]
+    fn max_depth_above_the_ceiling_is_rejected() {
+        let ceiling = ParseOptions::DEPTH_CEILING;
+        assert_eq!(with_max_depth(ceiling).max_depth(), ceiling);
+
+        let err = ParseOptions::default()
+            .with_max_depth(ceiling + 1)
+            .unwrap_err();
+        assert_eq!(err.max_depth, ceiling + 1);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "max_depth of {} is above the ceiling of {ceiling}",
+                ceiling + 1
+            )
+        );
+    }
+
+    #[test]
+    fn parse_within_depth_limit() {
+        let options = with_max_depth(3);
+        assert!(parse_with_options("[[[]]]", &options).is_ok());
+        assert!(parse_with_options(r#"{"a":[{"b":1}]}"#, &options).is_ok());
+        assert!(parse_with_options("[[], [], {}]", &with_max_depth(1)).is_err());
+        assert!(parse_with_options("[1, 2]", &with_max_depth(1)).is_ok());
+        assert!(parse_with_options("\"scalar\"", &with_max_depth(0)).is_ok());
+    }
+
+    #[test]
+    fn parse_depth_limit_exceeded() {
+        let err = parse_with_options(r#"{"a":[{"b":[1]}]}"#, &with_max_depth(3)).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::DepthLimitExceeded {
+                limit: 3,
+                span: Span {
+                    line: 1,
+                    col: 12,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn parse_default_depth_limit() {
+        let input = "[".repeat(129) + &"]".repeat(129);
+        assert!(matches!(
+            parse(&input),
+            Err(JsonError::DepthLimitExceeded { limit: 128, .. })
+        ));
+        let input = "[".repeat(128) + &"]".repeat(128);
+        assert!(parse(&input).is_ok());
+    }
+
+    #[test]
+    fn parse_deep_input_without_overflow() {
+        // Run on a small stack: the parser must not recurse per level.
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(|| {
+                let depth = ParseOptions::DEPTH_CEILING;
+                let options = with_max_depth(depth);
+
+                let err = parse_with_options(&"[".repeat(depth), &options).unwrap_err();
+                assert!(matches!(err, JsonError::UnexpectedEof { .. }));
+
+                let err = parse_with_options(&"[".repeat(100_000), &options).unwrap_err();
+                assert!(matches!(
+                    err,
+                    JsonError::DepthLimitExceeded { limit, .. } if limit == depth
+                ));
+
+                let input = r#"{"a":"#.repeat(depth) + "1" + &"}".repeat(depth);
+                let value = parse_with_options(&input, &options).unwrap();
+                let mut inner = &value;
+                for _ in 0..depth {
+                    inner = inner.get("a").unwrap();
+                }
+                assert_eq!(inner.as_f64(), Some(1.0));
+                value.drop_iteratively();
+            })
+            .unwrap();
+        handle
+            .join()
+            .expect("deep input should not overflow the stack");
+    }
+
+    #[test]
+    fn drop_deep_values_without_overflow() {
+        let handle = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(|| {
+                let mut value = JsonValue::Null;
+                for i in 0..100_000 {
+                    value = if i % 2 == 0 {
+                        JsonValue::Array(vec![value, JsonValue::from(i)])
+                    } else {
+                        JsonValue::from_iter([("a", value)])
+                    };
+                }
+                value.drop_iteratively();
+
+                let mut value = crate::JsonValueRef::Null;
+                for _ in 0..100_000 {
+                    value = crate::JsonValueRef::Array(vec![value]);
+                }
+                value.drop_iteratively();
+            })
+            .unwrap();
+        handle
+            .join()
+            .expect("dropping a deep value should not overflow the stack");
+    }
+
+    #[test]
+    fn values_at_the_depth_ceiling_are_usable() {
+        use std::{
+            cmp::Ordering,
+            hash::{DefaultHasher, Hash},
+        };
+
+        // The default stack size of a spawned thread.
+        let handle = std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(|| {
+                let depth = ParseOptions::DEPTH_CEILING / 2;
+                let input = r#"{"a":["#.repeat(depth) + "1" + &"]}".repeat(depth);
+                let options = with_max_depth(ParseOptions::DEPTH_CEILING);
+
+                let value = parse_with_options(&input, &options).unwrap();
+                let copy = value.clone();
+                assert_eq!(value, copy);
+                assert_eq!(value.cmp(&copy), Ordering::Equal);
+                value.hash(&mut DefaultHasher::new());
+                assert_eq!(
+                    parse_with_options(&value.to_string(), &options).unwrap(),
+                    value
+                );
+                assert!(format!("{value:?}").len() > input.len());
+
+                let borrowed = crate::parse_borrowed_with_options(&input, &options).unwrap();
+                assert_eq!(borrowed.clone().into_owned(), value);
+            })
+            .unwrap();
+        handle
+            .join()
+            .expect("values at the depth ceiling should not overflow the stack");
+    }
+
+    #[test]
+    fn parse_large_integers_exactly() {
+        let input = r#"{"id": 1234567890123456789, "max": 18446744073709551615, "min": -9223372036854775808}"#;
+        let value = parse(input).unwrap();
+        assert_eq!(
+            value.get("id").and_then(|v| v.as_i64()),
+            Some(1234567890123456789)
+        );
+        assert_eq!(value.get("max").and_then(|v| v.as_u64()), Some(u64::MAX));
+        assert_eq!(value.get("min").and_then(|v| v.as_i64()), Some(i64::MIN));
+        assert_eq!(value.get("min").and_then(|v| v.as_u64()), None);
+
+        let output = value.to_string();
+        assert!(output.contains("\"id\": 1234567890123456789,"));
+        assert!(output.contains("\"max\": 18446744073709551615,"));
+        assert!(output.contains("\"min\": -9223372036854775808\n"));
+    }
+
+    #[test]
+    fn parse_fractions_stay_floats() {
+        let value = parse("[2.0, 2.5e-3, -0, 7]").unwrap();
+        assert_eq!(value.get_index(0).and_then(|v| v.as_i64()), None);
+        assert_eq!(value.get_index(0).and_then(|v| v.as_f64()), Some(2.0));
+        assert_eq!(value.get_index(3).and_then(|v| v.as_f64()), Some(7.0));
+        assert_eq!(
+            value.to_string(),
+            "[\n    2.0,\n    0.0025,\n    -0.0,\n    7\n]"
+        );
+    }
+
+    #[test]
+    fn parse_raw_numbers() {
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..ParseOptions::default()
+        };
+        let input = "[1.50, 1E2, 123456789012345678901234567890, 0.1000000000000000000001]";
+        let value = parse_with_options(input, &options).unwrap();
+        assert_eq!(
+            value.to_string(),
+            "[\n    1.50,\n    1E2,\n    123456789012345678901234567890,\n    0.1000000000000000000001\n]"
+        );
+
+        let JsonValue::Number(big) = value.get_index(2).unwrap() else {
+            panic!("expected a number");
+        };
+        assert_eq!(big.raw(), Some("123456789012345678901234567890"));
+        assert!(big.is_f64());
+    }
+
+    fn with_duplicate_keys(duplicate_keys: DuplicateKeys) -> ParseOptions {
+        ParseOptions {
+            duplicate_keys,
+            ..ParseOptions::default()
+        }
+    }
+
+    const REPEATED_KEYS: &str = r#"{"a": 1, "b": [2], "a": 3, "b": 4, "a": [5], "c": 6}"#;
+
+    #[test]
+    fn duplicate_keys_error_by_default() {
+        let err = parse_with_options(REPEATED_KEYS, &with_duplicate_keys(DuplicateKeys::Error));
+        assert!(matches!(
+            err,
+            Err(JsonError::DuplicateKey {
+                span: Span { col: 20, .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn duplicate_keys_first_wins() {
+        let options = with_duplicate_keys(DuplicateKeys::FirstWins);
+        let value = parse_with_options(REPEATED_KEYS, &options).unwrap();
+        assert_eq!(
+            value.to_string(),
+            parse(r#"{"a": 1, "b": [2], "c": 6}"#).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_last_wins() {
+        let options = with_duplicate_keys(DuplicateKeys::LastWins);
+        let value = parse_with_options(REPEATED_KEYS, &options).unwrap();
+        assert_eq!(
+            value.to_string(),
+            parse(r#"{"a": [5], "b": 4, "c": 6}"#).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_collect() {
+        let options = with_duplicate_keys(DuplicateKeys::Collect);
+        let value = parse_with_options(REPEATED_KEYS, &options).unwrap();
+        let expected = r#"{"a": [1, 3, [5]], "b": [[2], 4], "c": 6}"#;
+        assert_eq!(value.to_string(), parse(expected).unwrap().to_string());
+
+        // Collected arrays are tracked per object.
+        let input = r#"[{"a": [1], "a": 2}, {"a": [3]}, {"x": {"a": 0, "a": 1}, "x": 2}]"#;
+        let value = parse_with_options(input, &options).unwrap();
+        let expected = r#"[{"a": [[1], 2]}, {"a": [3]}, {"x": [{"a": [0, 1]}, 2]}]"#;
+        assert_eq!(value, parse(expected).unwrap());
+    }
+
+    #[test]
+    fn render_error_with_context() {
+        let input = "[\n  1 2\n]";
+        let err = parse(input).unwrap_err();
+        assert_eq!(
+            err.render(input).to_string(),
+            "error[E0001]: expected ',' or ']', found a number\n \
+             --> line 2, col 5\n  \
+              |\n\
+             1 | [\n\
+             2 |   1 2\n  \
+              |     ^ expected ',' or ']'\n\
+             3 | ]\n  \
+              |"
+        );
+    }
+
+    #[test]
+    fn render_limits_context_lines() {
+        let input = "[\n1,\n2,\n3,\n4,\n5,\n6,\n7,\n8,\n9,\n10,\n11 12\n]";
+        let err = parse(input).unwrap_err();
+        let rendered = err.render(input).to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(
+            lines[0],
+            "error[E0001]: expected ',' or ']', found a number"
+        );
+        assert_eq!(lines[1], "  --> line 12, col 4");
+        assert_eq!(
+            &lines[2..],
+            [
+                "   |",
+                "10 | 9,",
+                "11 | 10,",
+                "12 | 11 12",
+                "   |    ^ expected ',' or ']'",
+                "13 | ]",
+                "   |",
+            ]
+        );
+    }
+
+    #[test]
+    fn render_caret_after_tabs_and_non_ascii() {
+        let input = "{\"é\":\ttru}";
+        let err = parse(input).unwrap_err();
+        let rendered = err.render(input).to_string();
+        assert!(
+            rendered.starts_with("error[E0011]: unexpected character '}'\n --> line 1, col 10\n")
+        );
+        assert!(rendered.contains("\n  |      \t   ^\n"));
+    }
+
+    #[test]
+    fn render_crlf_source() {
+        let input = "{\r\n  \"a\": 1,\r\n  \"a\": 2\r\n}";
+        let err = parse(input).unwrap_err();
+        let rendered = err.render(input).to_string();
+        assert!(rendered.contains("\n3 |   \"a\": 2\n  |   ^\n4 | }\n"));
+        assert!(!rendered.contains('\r'));
+    }
+
+    #[test]
+    fn render_at_end_of_input() {
+        let input = "[1,";
+        let err = parse(input).unwrap_err();
+        assert_eq!(
+            err.render(input).to_string(),
+            "error[E0002]: unexpected end of input, expected a value\n \
+             --> line 1, col 4\n  \
+              |\n\
+             1 | [1,\n  \
+              |    ^ expected a value\n  \
+              |"
+        );
+    }
+
+    #[test]
+    fn render_with_color() {
+        let input = "[1 2]";
+        let err = parse(input).unwrap_err();
+        let plain = err.render(input).to_string();
+        let colored = err.render(input).with_color(true).to_string();
+        assert!(colored.starts_with("\x1b[1;31merror[E0001]\x1b[0m\x1b[1m: expected"));
+        assert!(colored.contains("\x1b[1;31m^ expected ',' or ']'\x1b[0m"));
+        assert!(!plain.contains('\x1b'));
+    }
+
+    #[test]
+    fn error_codes_are_stable() {
+        let cases = [
+            ("[1 2]", ErrorKind::UnexpectedToken, "E0001"),
+            ("[1,", ErrorKind::UnexpectedEof, "E0002"),
+            ("01", ErrorKind::InvalidNumber, "E0003"),
+            (r#"{"a":1,"a":2}"#, ErrorKind::DuplicateKey, "E0005"),
+            (r#""\q""#, ErrorKind::InvalidEscape, "E0007"),
+            (r#""\udc00""#, ErrorKind::LoneSurrogate, "E0008"),
+            ("\"abc", ErrorKind::UnterminatedString, "E0009"),
+            ("nul", ErrorKind::TruncatedLiteral, "E0010"),
+            ("[x]", ErrorKind::UnexpectedCharacter, "E0011"),
+            ("1 2", ErrorKind::TrailingCharacters, "E0012"),
+        ];
+        for (input, kind, code) in cases {
+            let err = parse(input).unwrap_err();
+            assert_eq!((err.kind(), err.code()), (kind, code), "{input:?}");
+        }
+
+        let err = parse_bytes(b"\"\xff\"").unwrap_err();
+        assert_eq!((err.kind(), err.code()), (ErrorKind::InvalidUtf8, "E0004"));
+        let err = parse_with_options("[[1]]", &with_max_depth(1)).unwrap_err();
+        assert_eq!(
+            (err.kind(), err.code()),
+            (ErrorKind::DepthLimitExceeded, "E0006")
+        );
+        assert_eq!(ErrorKind::Custom.code(), "E0013");
+    }
+
+    #[test]
+    fn error_to_json() {
+        let err = parse("[1 2]").unwrap_err();
+        let json = err.to_json();
+        assert_eq!(json.get("code").and_then(|v| v.as_str()), Some("E0001"));
+        assert_eq!(
+            json.get("message").and_then(|v| v.as_str()),
+            Some("expected ',' or ']', found a number")
+        );
+
+        let span = json.get("span").unwrap();
+        assert_eq!(span.get("offset").and_then(|v| v.as_f64()), Some(3.0));
+        assert_eq!(span.get("line").and_then(|v| v.as_f64()), Some(1.0));
+        assert_eq!(span.get("col").and_then(|v| v.as_f64()), Some(4.0));
+
+        // The serialised form is itself valid JSON.
+        assert_eq!(parse(&json.to_string()).ok(), Some(json));
+    }
+
+    #[test]
+    fn unterminated_string_message() {
+        let input = "[\"abc";
+        let err = parse(input).unwrap_err();
+        assert_eq!(err.to_string(), "unterminated string at line 1, col 2");
+
+        let rendered = err.render(input).to_string();
+        assert_eq!(
+            rendered.lines().next(),
+            Some("error[E0009]: unterminated string")
+        );
+        assert_eq!(
+            err.to_json().get("message").and_then(|v| v.as_str()),
+            Some("unterminated string")
+        );
+    }
+
+    #[test]
+    fn diagnostic_to_json() {
+        let (_, diagnostics) = parse_with_recovery("[1,,2 3]");
+        let codes: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_json())
+            .map(|json| {
+                json.get("code")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            })
+            .collect();
+        assert_eq!(
+            codes,
+            [Some("E0001".to_string()), Some("E0001".to_string())]
+        );
+    }
+
+    // Parses `input` with recovery and returns the value alongside the line
+    // and column of each diagnostic.
+    fn recover(input: &str) -> (Option<JsonValue>, Vec<(usize, usize)>) {
+        let (value, diagnostics) = parse_with_recovery(input);
+        let positions = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.span().unwrap().line,
+                    diagnostic.span().unwrap().col,
+                )
+            })
+            .collect();
+        (value, positions)
+    }
+
+    #[test]
+    fn recovery_of_valid_input() {
+        let input = r#"{"a": [1, {"b": null}], "c": "é"}"#;
+        let (value, diagnostics) = parse_with_recovery(input);
+        assert!(diagnostics.is_empty());
+        assert_eq!(value, parse(input).ok());
+    }
+
+    #[test]
+    fn recovery_reports_every_problem() {
+        let input = "{\n  \"name\": \"rsjson\"\n  \"tags\": [\"a\" \"b\",],\n  \"size\": 01,\n  \"name\": true\n}";
+        let (value, diagnostics) = parse_with_recovery(input);
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected ',' or '}', found a string at line 3, col 3",
+                "expected ',' or ']', found a string at line 3, col 16",
+                "expected a value, found ']' at line 3, col 20",
+                "invalid number at line 4, col 12",
+                "duplicate key \"name\" at line 5, col 3",
+            ]
+        );
+        assert_eq!(
+            value,
+            parse(r#"{"tags": ["a", "b"], "size": null, "name": true}"#).ok()
+        );
+    }
+
+    #[test]
+    fn recovery_inserts_placeholders() {
+        assert_eq!(recover("[1,,2]"), (parse("[1,null,2]").ok(), vec![(1, 4)]));
+        assert_eq!(recover("[,1]"), (parse("[null,1]").ok(), vec![(1, 2)]));
+        assert_eq!(
+            recover(r#"{"a":}"#),
+            (parse(r#"{"a":null}"#).ok(), vec![(1, 6)])
+        );
+        assert_eq!(
+            recover(r#"{"a" , "b": 1}"#),
+            (parse(r#"{"a":null,"b":1}"#).ok(), vec![(1, 6)])
+        );
+        assert_eq!(
+            recover(r#"{"a""#),
+            (parse(r#"{"a":null}"#).ok(), vec![(1, 5)])
+        );
+    }
+
+    #[test]
+    fn recovery_drops_trailing_commas() {
+        assert_eq!(recover("[1,]"), (parse("[1]").ok(), vec![(1, 4)]));
+        assert_eq!(
+            recover(r#"{"a":1,}"#),
+            (parse(r#"{"a":1}"#).ok(), vec![(1, 8)])
+        );
+    }
+
+    #[test]
+    fn recovery_assumes_missing_separators() {
+        assert_eq!(recover("[1 2]"), (parse("[1,2]").ok(), vec![(1, 4)]));
+        assert_eq!(
+            recover(r#"{"a" 1, "b": 2}"#),
+            (parse(r#"{"a":1,"b":2}"#).ok(), vec![(1, 6)])
+        );
+        assert_eq!(
+            recover(r#"{"a": 1 "b": [true false]}"#),
+            (
+                parse(r#"{"a":1,"b":[true,false]}"#).ok(),
+                vec![(1, 9), (1, 20)]
+            )
+        );
+    }
+
+    #[test]
+    fn recovery_skips_stray_colons() {
+        assert_eq!(recover("[:]"), (parse("[]").ok(), vec![(1, 2)]));
+        assert_eq!(
+            recover(r#"{"a"::1}"#),
+            (parse(r#"{"a":1}"#).ok(), vec![(1, 6)])
+        );
+        assert_eq!(recover("[:1]"), (parse("[1]").ok(), vec![(1, 2)]));
+    }
+
+    #[test]
+    fn recovery_closes_unclosed_containers() {
+        assert_eq!(
+            recover(r#"{"a": [1, 2}"#),
+            (parse(r#"{"a":[1,2]}"#).ok(), vec![(1, 12)])
+        );
+        assert_eq!(recover("[1, 2}"), (parse("[1,2]").ok(), vec![(1, 6)]));
+        assert_eq!(
+            recover("[1, [2, 3"),
+            (parse("[1,[2,3]]").ok(), vec![(1, 10)])
+        );
+        assert_eq!(
+            recover(r#"{"a": {"#),
+            (parse(r#"{"a":{}}"#).ok(), vec![(1, 8)])
+        );
+    }
+
+    #[test]
+    fn recovery_skips_invalid_tokens() {
+        assert_eq!(
+            recover("[1, x, 3]"),
+            (parse("[1,null,3]").ok(), vec![(1, 5)])
+        );
+        assert_eq!(recover("[-, 3]"), (parse("[null,3]").ok(), vec![(1, 3)]));
+        assert_eq!(
+            recover("[\"a\u{1}b\", \"c\"]"),
+            (parse(r#"[null,"c"]"#).ok(), vec![(1, 4)])
+        );
+        assert_eq!(
+            recover(r#"{x: 1, "b": [2], 3: 4, "c": 5}"#),
+            (parse(r#"{"b":[2],"c":5}"#).ok(), vec![(1, 2), (1, 18)])
+        );
+        assert_eq!(
+            recover(r#"{"a": "\q", "b": "\ud800"}"#),
+            (parse(r#"{"a":null,"b":null}"#).ok(), vec![(1, 8), (1, 19)])
+        );
+    }
+
+    #[test]
+    fn recovery_without_a_value() {
+        assert_eq!(recover(""), (None, vec![(1, 1)]));
+        assert_eq!(recover("  \n "), (None, vec![(2, 2)]));
+        assert_eq!(recover("]"), (None, vec![(1, 1), (1, 2)]));
+    }
+
+    #[test]
+    fn recovery_reports_trailing_characters() {
+        assert_eq!(recover("[1] 2"), (parse("[1]").ok(), vec![(1, 5)]));
+        assert_eq!(recover(",1"), (parse("1").ok(), vec![(1, 1)]));
+    }
+
+    #[test]
+    fn recovery_skips_containers_past_depth_limit() {
+        let input = "[".repeat(130) + &"]".repeat(130);
+        let input = format!("[{input}, 1 2]");
+        let (value, diagnostics) = parse_with_recovery(&input);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(matches!(
+            diagnostics[0].error,
+            JsonError::DepthLimitExceeded {
+                limit: 128,
+                span: Span { col: 129, .. }
+            }
+        ));
+        assert!(matches!(
+            diagnostics[1].error,
+            JsonError::UnexpectedToken { .. }
+        ));
+
+        let mut inner = value.as_ref().unwrap();
+        assert_eq!(inner.get_index(1).and_then(|v| v.as_f64()), Some(1.0));
+        assert_eq!(inner.get_index(2).and_then(|v| v.as_f64()), Some(2.0));
+        for _ in 0..127 {
+            inner = inner.get_index(0).unwrap();
+        }
+        assert_eq!(inner, &JsonValue::Array(vec![JsonValue::Null]));
+    }
+
+    #[test]
+    fn recovery_handles_truncated_documents() {
+        for document in TRUNCATION_CORPUS {
+            for end in (0..document.len()).filter(|&end| document.is_char_boundary(end)) {
+                let prefix = &document[..end];
+                let (value, diagnostics) = parse_with_recovery(prefix);
+                assert_eq!(diagnostics.is_empty(), parse(prefix).is_ok(), "{prefix:?}");
+                assert_eq!(value.is_none(), prefix.trim().is_empty(), "{prefix:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_null() {
+        let result = parse("null").unwrap();
+        assert_eq!(result.to_string(), "null");
+    }
+
+    #[test]
+    fn test_display_boolean_true() {
+        let result = parse("true").unwrap();
+        assert_eq!(result.to_string(), "true");
+    }
+
+    #[test]
+    fn test_display_boolean_false() {
+        let result = parse("false").unwrap();
+        assert_eq!(result.to_string(), "false");
+    }
+
+    #[test]
+    fn test_display_number() {
+        let result = parse("123").unwrap();
+        assert_eq!(result.to_string(), "123");
+    }
+
+    #[test]
+    fn test_display_string() {
+        let result = parse(r#""hello""#).unwrap();
+        assert_eq!(result.to_string(), "\"hello\"");
+    }
+
+    #[test]
+    fn test_display_empty_array() {
+        let result = parse("[]").unwrap();
+        let output = result.to_string();
+        assert!(output.contains("["));
+        assert!(output.contains("]"));
+    }
+
+    #[test]
+    fn test_display_empty_object() {
+        let result = parse("{}").unwrap();
+        let output = result.to_string();
+        assert!(output.contains("{"));
+        assert!(output.contains("}"));
+    }
+
+    #[test]
+    fn test_display_simple_array() {
+        let result = parse("[1,2,3]").unwrap();
+        let output = result.to_string();
+        assert!(output.contains("1"));
+        assert!(output.contains("2"));
+        assert!(output.contains("3"));
     }
 
     #[test]
@@ -453,4 +2328,291 @@ mod parser_tests {
         assert!(output.contains("rust"));
         assert!(output.contains("python"));
     }
+
+    #[test]
+    fn test_display_preserves_key_order() {
+        let input = "{\n    \"zeta\": 1,\n    \"alpha\": {\n        \"y\": true,\n        \"x\": null\n    },\n    \"mu\": []\n}";
+        let output = parse(input).unwrap().to_string();
+        assert_eq!(output, input);
+        assert_eq!(parse(&output).unwrap().to_string(), input);
+    }
+
+    #[test]
+    fn test_edit_in_place() {
+        let mut value = parse(r#"{"name": "svc", "ports": [80], "debug": true}"#).unwrap();
+
+        *value.get_mut("name").unwrap() = JsonValue::String("api".to_string());
+        *value
+            .get_mut("ports")
+            .and_then(|ports| ports.get_index_mut(0))
+            .unwrap() = JsonValue::Number(8080.into());
+        value
+            .get_mut("ports")
+            .unwrap()
+            .push(JsonValue::Number(443.into()));
+        assert_eq!(value.insert("replicas", JsonValue::Number(3.into())), None);
+        assert_eq!(value.remove("debug"), Some(JsonValue::Boolean(true)));
+        assert_eq!(value.remove("debug"), None);
+        assert!(value.get_mut("missing").is_none());
+
+        let expected = r#"{"name": "api", "ports": [8080, 443], "replicas": 3}"#;
+        assert_eq!(value, parse(expected).unwrap());
+    }
+
+    #[test]
+    fn test_take_leaves_null() {
+        let mut value = parse(r#"{"items": [1, 2]}"#).unwrap();
+        let items = value.get_mut("items").unwrap().take();
+        assert_eq!(items, parse("[1, 2]").unwrap());
+        assert_eq!(value.get("items"), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot push an element onto an object")]
+    fn test_push_onto_non_array_panics() {
+        parse("{}").unwrap().push(JsonValue::Null);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot insert a member into an array")]
+    fn test_insert_into_non_object_panics() {
+        parse("[]").unwrap().insert("a", JsonValue::Null);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let value = parse(r#"{"a": [{"b": 1}, 2], "c/d": 3, "e~f": 4, "": 5}"#).unwrap();
+
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/a/0/b").and_then(JsonValue::as_i64), Some(1));
+        assert_eq!(value.pointer("/a/1").and_then(JsonValue::as_i64), Some(2));
+        assert_eq!(value.pointer("/c~1d").and_then(JsonValue::as_i64), Some(3));
+        assert_eq!(value.pointer("/e~0f").and_then(JsonValue::as_i64), Some(4));
+        assert_eq!(value.pointer("/").and_then(JsonValue::as_i64), Some(5));
+
+        for missing in ["a", "/a/2", "/a/01", "/a/-", "/a/+1", "/a/0/b/c", "/x"] {
+            assert_eq!(value.pointer(missing), None, "{missing}");
+        }
+    }
+
+    #[test]
+    fn test_pointer_mut() {
+        let mut value = parse(r#"{"server": {"ports": [80, 443]}}"#).unwrap();
+        *value.pointer_mut("/server/ports/1").unwrap() = JsonValue::Number(8443.into());
+        value
+            .pointer_mut("/server")
+            .unwrap()
+            .insert("host", JsonValue::String("localhost".to_string()));
+
+        let expected = r#"{"server": {"ports": [80, 8443], "host": "localhost"}}"#;
+        assert_eq!(value, parse(expected).unwrap());
+        assert!(value.pointer_mut("/server/ports/2").is_none());
+    }
+
+    #[test]
+    fn test_index_chains() {
+        let value = parse(r#"{"a": [0, 1, {"b": "deep"}], "n": null}"#).unwrap();
+
+        assert_eq!(value["a"][2]["b"].as_str(), Some("deep"));
+        assert_eq!(value["a"][1].as_i64(), Some(1));
+        assert!(value["missing"]["b"][7].is_null());
+        assert!(value["a"][3].is_null());
+        assert!(value["a"]["b"].is_null());
+        assert!(value[0].is_null());
+        assert!(value["n"].is_null());
+    }
+
+    #[test]
+    fn test_index_matches_accessors() {
+        let result = parse(r#"{"a":[1,2,{"b":true}],"c":{"d":{"e":"deep"}}}"#).unwrap();
+        assert_eq!(
+            result["a"][2]["b"],
+            *result
+                .get("a")
+                .and_then(|v| v.get_index(2))
+                .and_then(|v| v.get("b"))
+                .unwrap()
+        );
+        assert_eq!(result["c"]["d"]["e"].as_str(), Some("deep"));
+
+        let result = parse(r#"[[1,2],[{"a":1},{"b\nc":["x\ty"]}]]"#).unwrap();
+        assert_eq!(result[0][1].as_f64(), Some(2.0));
+        assert_eq!(result[1][0]["a"].as_f64(), Some(1.0));
+        assert_eq!(result[1][1]["b\nc"][0].as_str(), Some("x\ty"));
+    }
+
+    #[test]
+    fn test_index_mut_creates_members() {
+        let mut value = JsonValue::Null;
+        value["server"]["host"] = JsonValue::String("localhost".to_string());
+        value["server"]["ports"] = parse("[80, 443]").unwrap();
+        value["server"]["ports"][1] = JsonValue::Number(8443.into());
+
+        let expected = r#"{"server": {"host": "localhost", "ports": [80, 8443]}}"#;
+        assert_eq!(value, parse(expected).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index an array with a key")]
+    fn test_index_mut_key_on_array_panics() {
+        parse("[]").unwrap()["a"] = JsonValue::Null;
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 is out of bounds for an array of length 2")]
+    fn test_index_mut_out_of_bounds_panics() {
+        parse("[1, 2]").unwrap()[2] = JsonValue::Null;
+    }
+
+    #[test]
+    fn test_clone_is_independent() {
+        let original = parse(r#"{"a": [1, {"b": "c"}]}"#).unwrap();
+        let mut copy = original.clone();
+        copy["a"][1]["b"] = JsonValue::Null;
+        assert_eq!(original["a"][1]["b"].as_str(), Some("c"));
+        assert_ne!(copy, original);
+    }
+
+    #[test]
+    fn test_values_in_hash_set() {
+        use std::collections::HashSet;
+
+        let set: HashSet<JsonValue> = [
+            r#"{"a": 1, "b": [true, null]}"#,
+            r#"{"b": [true, null], "a": 1}"#,
+            r#"{"a": 1.0, "b": [true, null]}"#,
+            "0.0",
+            "-0.0",
+            "0",
+        ]
+        .into_iter()
+        .map(|input| parse(input).unwrap())
+        .collect();
+
+        // Key order does not matter, but `1` and `1.0`, and `0.0` and `-0.0`,
+        // are different numbers.
+        assert_eq!(set.len(), 5);
+        assert!(set.contains(&parse(r#"{"b": [true, null], "a": 1}"#).unwrap()));
+    }
+
+    #[test]
+    fn test_nan_equals_itself() {
+        let nan = JsonValue::from(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert_ne!(nan, JsonValue::from(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_total_order_across_kinds() {
+        let mut values: Vec<JsonValue> = [
+            r#"{"a": 1}"#,
+            r#"{"a": 0, "b": 0}"#,
+            "[1, 2]",
+            "[1]",
+            "[]",
+            r#""b""#,
+            r#""a""#,
+            "2",
+            "1.5",
+            "true",
+            "false",
+            "null",
+        ]
+        .into_iter()
+        .map(|input| parse(input).unwrap())
+        .collect();
+        values.push(JsonValue::from(f64::NAN));
+        values.sort();
+
+        let sorted: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        let expected = [
+            "null", "false", "true", "1.5", "2", "null", "\"a\"", "\"b\"", "[]",
+        ];
+        assert_eq!(sorted[..9], expected);
+        assert_eq!(values[5], JsonValue::from(f64::NAN));
+        assert_eq!(values[9], parse("[1]").unwrap());
+        assert_eq!(values[10], parse("[1, 2]").unwrap());
+        assert_eq!(values[11], parse(r#"{"a": 0, "b": 0}"#).unwrap());
+        assert_eq!(values[12], parse(r#"{"a": 1}"#).unwrap());
+    }
+
+    #[test]
+    fn test_kind_and_predicates() {
+        let value = parse(r#"[null, true, 1, "s", [], {}]"#).unwrap();
+        let kinds: Vec<JsonKind> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(JsonValue::kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                JsonKind::Null,
+                JsonKind::Boolean,
+                JsonKind::Number,
+                JsonKind::String,
+                JsonKind::Array,
+                JsonKind::Object
+            ]
+        );
+        assert_eq!(
+            format!("expected {}, got {}", JsonKind::Array, value[5].kind()),
+            "expected array, got object"
+        );
+
+        assert!(value[0].is_null() && value[1].is_boolean() && value[2].is_number());
+        assert!(value[3].is_string() && value[4].is_array() && value[5].is_object());
+        assert!(!value[3].is_number() && !value[4].is_object() && !value[5].is_array());
+    }
+
+    #[test]
+    fn test_number_accessors_check_range() {
+        let value = parse("[-1, 18446744073709551615, 1.5, 1e3]").unwrap();
+        assert!(value[0].is_i64() && !value[0].is_u64());
+        assert_eq!(value[1].as_i64(), None);
+        assert_eq!(value[1].as_u64(), Some(u64::MAX));
+        assert!(value[2].is_f64() && !value[2].is_i64());
+        assert_eq!(value[3].as_i64(), None);
+        assert_eq!(value[3].as_number().map(Number::as_f64), Some(1000.0));
+        assert!(!JsonValue::from("1").is_f64());
+    }
+
+    #[test]
+    fn test_container_accessors() {
+        let mut value = parse(r#"{"list": [1, 2, 3], "empty": {}, "s": "abc"}"#).unwrap();
+        assert_eq!(value.len(), 3);
+        assert_eq!(value["list"].len(), 3);
+        assert!(value["empty"].is_empty());
+        assert!(value["s"].is_empty());
+        assert_eq!(value.as_object().map(Map::len), Some(3));
+        assert_eq!(value["list"].as_object(), None);
+
+        value["list"]
+            .as_array_mut()
+            .unwrap()
+            .retain(|n| n.as_i64() != Some(2));
+        value.as_object_mut().unwrap().remove("empty");
+
+        let mut object = value.into_object().unwrap();
+        assert_eq!(
+            object
+                .remove("list")
+                .and_then(JsonValue::into_array)
+                .map(|a| a.len()),
+            Some(2)
+        );
+        assert_eq!(
+            object
+                .remove("s")
+                .and_then(JsonValue::into_string)
+                .as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            JsonValue::from(5).into_number().and_then(|n| n.as_u64()),
+            Some(5)
+        );
+        assert_eq!(JsonValue::Null.into_array(), None);
+    }
 }
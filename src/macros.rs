@@ -0,0 +1,211 @@
+/// Builds a [`JsonValue`](crate::JsonValue) from JSON syntax.
+///
+/// Values that are not `null`, an array or an object are Rust expressions
+/// converted with `JsonValue::from`, so variables and calls can be mixed into
+/// the document. Object keys are string literals, or any expression that
+/// converts into a `String` when wrapped in parentheses.
+///
+/// ```
+/// use rsjson::json;
+///
+/// let name = "rsjson";
+/// let value = json!({
+///     "name": name.clone(),
+///     "tags": ["json", "parser"],
+///     "stars": 40 + 2,
+///     "license": null,
+///     (format!("v{}", 1)): { "stable": true },
+/// });
+///
+/// assert_eq!(value["stars"].as_i64(), Some(42));
+/// assert_eq!(value["v1"]["stable"].as_bool(), Some(true));
+/// ```
+#[macro_export]
+macro_rules! json {
+    ($($json:tt)+) => {
+        $crate::json_internal!($($json)+)
+    };
+}
+
+// Munches the tokens of an array or object one element or member at a time,
+// as `expr` fragments cannot be followed by arbitrary tokens. Elements are
+// gathered in `[...]` and an object's key in `(...)` until its value is read.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // Arrays.
+    (@array [$($elems:expr,)*]) => {
+        ::std::vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        ::std::vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!({$($object)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+    (@array [$($elems:expr),*] $unexpected:tt $($rest:tt)*) => {
+        $crate::json_unexpected!($unexpected)
+    };
+
+    // Objects. The remaining tokens are passed twice so that errors can point
+    // at the offending token.
+    (@object $object:ident () () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        $object.insert(($($key)+).into(), $value);
+        $crate::json_internal!(@object $object () ($($rest)*) ($($rest)*));
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr) $unexpected:tt $($rest:tt)*) => {
+        $crate::json_unexpected!($unexpected);
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        $object.insert(($($key)+).into(), $value);
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(null)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(
+            @object $object [$($key)+] ($crate::json_internal!([$($array)*])) $($rest)*
+        );
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(
+            @object $object [$($key)+] ($crate::json_internal!({$($map)*})) $($rest)*
+        );
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(
+            @object $object [$($key)+] ($crate::json_internal!($value)) , $($rest)*
+        );
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)));
+    };
+    // A key without a value: fail on an empty invocation.
+    (@object $object:ident ($($key:tt)+) (:) $copy:tt) => {
+        $crate::json_internal!();
+    };
+    (@object $object:ident ($($key:tt)+) () $copy:tt) => {
+        $crate::json_internal!();
+    };
+    (@object $object:ident () (: $($rest:tt)*) ($colon:tt $($copy:tt)*)) => {
+        $crate::json_unexpected!($colon);
+    };
+    (@object $object:ident ($($key:tt)*) (, $($rest:tt)*) ($comma:tt $($copy:tt)*)) => {
+        $crate::json_unexpected!($comma);
+    };
+    (@object $object:ident () (($key:expr) : $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object ($key) (: $($rest)*) (: $($rest)*));
+    };
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object ($($key)* $tt) ($($rest)*) ($($rest)*));
+    };
+
+    // Values.
+    (null) => {
+        $crate::JsonValue::Null
+    };
+    ([]) => {
+        $crate::JsonValue::Array(::std::vec::Vec::new())
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::JsonValue::Array($crate::json_internal!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::JsonValue::Object($crate::Map::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::JsonValue::Object({
+            let mut object = $crate::Map::new();
+            $crate::json_internal!(@object object () ($($tt)+) ($($tt)+));
+            object
+        })
+    };
+    ($other:expr) => {
+        $crate::JsonValue::from($other)
+    };
+}
+
+// Has no rules, so invoking it with a token reports "no rules expected the
+// token" at that token.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_unexpected {
+    () => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    #[test]
+    fn literals() {
+        assert_eq!(json!(null), JsonValue::Null);
+        assert_eq!(json!(true), JsonValue::Boolean(true));
+        assert_eq!(json!(-1.5).as_f64(), Some(-1.5));
+        assert_eq!(json!("text").as_str(), Some("text"));
+        assert_eq!(json!([]), parse("[]").unwrap());
+        assert_eq!(json!({}), parse("{}").unwrap());
+    }
+
+    #[test]
+    fn nested_documents() {
+        let value = json!({
+            "a": [1, true, null],
+            "b": { "c": [[], {}], "d": "e" },
+            "f": null
+        });
+        let expected = r#"{"a": [1, true, null], "b": {"c": [[], {}], "d": "e"}, "f": null}"#;
+        assert_eq!(value, parse(expected).unwrap());
+        assert_eq!(
+            json!([null, [null], {"x": null},]),
+            parse(r#"[null, [null], {"x": null}]"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn interpolated_expressions() {
+        let name = String::from("rsjson");
+        let ports = vec![80, 443];
+        let key = "dynamic";
+        let value = json!({
+            "name": name.clone(),
+            "ports": ports,
+            "next": 1 + 2,
+            "missing": None::<bool>,
+            (key): [key.len(), -(name.len() as i64 - 2)],
+            "nested": json!({ "ok": true }),
+        });
+
+        let expected = r#"{
+            "name": "rsjson", "ports": [80, 443], "next": 3, "missing": null,
+            "dynamic": [7, -4], "nested": {"ok": true}
+        }"#;
+        assert_eq!(value, parse(expected).unwrap());
+
+        // Members keep the order they are written in.
+        let keys: Vec<&str> = match &value {
+            JsonValue::Object(map) => map.keys().map(String::as_str).collect(),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            keys,
+            ["name", "ports", "next", "missing", "dynamic", "nested"]
+        );
+    }
+}
@@ -0,0 +1,658 @@
+use crate::JsonValue;
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{BuildHasher, Hash, Hasher, RandomState},
+    slice, vec,
+};
+
+/// The members of a JSON object, kept in the order they were inserted so that
+/// objects print the way they were read.
+///
+/// Lookups go through a hash table of positions, so `get` and `insert` take
+/// constant time while `remove` shifts the members after the removed one.
+/// Each key is stored once, in its member.
+///
+/// Like JSON objects, maps compare and hash without regard to order: two maps
+/// with the same members are equal, hash the same and compare as their
+/// members sorted by key.
+///
+/// The key and value types default to those of [`JsonValue`]. Objects of a
+/// [`JsonValueRef`](crate::JsonValueRef) use keys borrowed from the input.
+#[derive(Clone)]
+pub struct Map<K = String, V = JsonValue> {
+    entries: Vec<(K, V)>,
+    // An open-addressed table with linear probing, at most half full, whose
+    // length is zero or a power of two.
+    slots: Vec<Option<Slot>>,
+    hasher: RandomState,
+}
+
+// The position of a member in `entries` and the hash of its key.
+#[derive(Clone, Copy)]
+struct Slot {
+    hash: u64,
+    pos: usize,
+}
+
+impl<K, V> Map<K, V> {
+    pub fn new() -> Map<K, V> {
+        Map::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Map<K, V> {
+        let slots = match capacity {
+            0 => 0,
+            _ => (capacity * 2).next_power_of_two().max(8),
+        };
+        Map {
+            entries: Vec::with_capacity(capacity),
+            slots: vec![None; slots],
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.slots.fill(None);
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+
+    fn sorted(&self) -> Vec<&(K, V)>
+    where
+        K: Ord,
+    {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    // The first free slot at or after the home slot of `hash`.
+    fn free_slot(slots: &[Option<Slot>], hash: u64) -> usize {
+        let mask = slots.len() - 1;
+        let mut i = hash as usize & mask;
+        while slots[i].is_some() {
+            i = (i + 1) & mask;
+        }
+        i
+    }
+
+    fn grow(&mut self) {
+        let len = (self.slots.len() * 2).max(8);
+        let slots = std::mem::replace(&mut self.slots, vec![None; len]);
+        for slot in slots.into_iter().flatten() {
+            let i = Self::free_slot(&self.slots, slot.hash);
+            self.slots[i] = Some(slot);
+        }
+    }
+
+    // Appends a member whose key is not in the map and returns its position.
+    fn push(&mut self, hash: u64, key: K, value: V) -> usize {
+        if (self.entries.len() + 1) * 2 > self.slots.len() {
+            self.grow();
+        }
+
+        let pos = self.entries.len();
+        let i = Self::free_slot(&self.slots, hash);
+        self.slots[i] = Some(Slot { hash, pos });
+        self.entries.push((key, value));
+        pos
+    }
+
+    // Removes the member in slot `i`, shifting the members after it.
+    fn remove_slot(&mut self, i: usize) -> (K, V) {
+        let Some(removed) = self.slots[i] else {
+            unreachable!("removed an empty slot");
+        };
+
+        // Later slots in the same run move back into the hole unless their
+        // home slot lies after it, so that every key stays reachable from
+        // its home slot without passing an empty one.
+        let mask = self.slots.len() - 1;
+        let mut hole = i;
+        let mut next = i;
+        self.slots[hole] = None;
+        loop {
+            next = (next + 1) & mask;
+            let Some(slot) = self.slots[next] else {
+                break;
+            };
+            let home = slot.hash as usize & mask;
+            if next.wrapping_sub(home) & mask >= next.wrapping_sub(hole) & mask {
+                self.slots[hole] = Some(slot);
+                self.slots[next] = None;
+                hole = next;
+            }
+        }
+
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.pos > removed.pos {
+                slot.pos -= 1;
+            }
+        }
+        self.entries.remove(removed.pos)
+    }
+}
+
+impl<K: Borrow<str>, V> Map<K, V> {
+    // The slot and position of the member for `key`, whose hash is `hash`.
+    fn find(&self, key: &str, hash: u64) -> Option<(usize, usize)> {
+        if self.slots.is_empty() {
+            return None;
+        }
+
+        let mask = self.slots.len() - 1;
+        let mut i = hash as usize & mask;
+        while let Some(slot) = self.slots[i] {
+            if slot.hash == hash && self.entries[slot.pos].0.borrow() == key {
+                return Some((i, slot.pos));
+            }
+            i = (i + 1) & mask;
+        }
+        None
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        let (_, pos) = self.find(key, self.hasher.hash_one(key))?;
+        Some(pos)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let i = self.position(key)?;
+        Some(&self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let i = self.position(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    /// Inserts a member and returns the value it replaced. A key that is
+    /// already present keeps its position and only has its value replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hasher.hash_one(key.borrow());
+        if let Some((_, i)) = self.find(key.borrow(), hash) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+
+        self.push(hash, key, value);
+        None
+    }
+
+    /// Removes a member and returns its value, keeping the order of the rest.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let (slot, _) = self.find(key, self.hasher.hash_one(key))?;
+        Some(self.remove_slot(slot).1)
+    }
+
+    /// Gets the member for `key` to read, update or insert in place.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let hash = self.hasher.hash_one(key.borrow());
+        match self.find(key.borrow(), hash) {
+            Some((slot, i)) => Entry::Occupied(OccupiedEntry { map: self, slot, i }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                key,
+                hash,
+            }),
+        }
+    }
+}
+
+/// A member of a [`Map`], returned by [`Map::entry`].
+pub enum Entry<'a, K = String, V = JsonValue> {
+    Vacant(VacantEntry<'a, K, V>),
+    Occupied(OccupiedEntry<'a, K, V>),
+}
+
+impl<'a, K: Borrow<str>, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Vacant(entry) => entry.key(),
+            Entry::Occupied(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the member is missing and returns its value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+
+    /// Calls `f` on the value if the member is present.
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Entry<'a, K, V> {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+/// A missing member of a [`Map`]. Inserting it appends it to the map.
+pub struct VacantEntry<'a, K = String, V = JsonValue> {
+    map: &'a mut Map<K, V>,
+    key: K,
+    hash: u64,
+}
+
+impl<'a, K: Borrow<str>, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let i = self.map.push(self.hash, self.key, value);
+        &mut self.map.entries[i].1
+    }
+}
+
+/// A member present in a [`Map`].
+pub struct OccupiedEntry<'a, K = String, V = JsonValue> {
+    map: &'a mut Map<K, V>,
+    slot: usize,
+    i: usize,
+}
+
+impl<'a, K: Borrow<str>, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.map.entries[self.i].0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map.entries[self.i].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.entries[self.i].1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.entries[self.i].1
+    }
+
+    /// Replaces the value, keeping the member's position, and returns the old
+    /// one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the member, keeping the order of the rest.
+    pub fn remove(self) -> V {
+        self.map.remove_slot(self.slot).1
+    }
+}
+
+impl<K, V> Default for Map<K, V> {
+    fn default() -> Map<K, V> {
+        Map::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Map<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Maps are equal when they have the same members, in any order, as JSON
+/// objects are unordered.
+impl<K: Borrow<str>, V: PartialEq> PartialEq for Map<K, V> {
+    fn eq(&self, other: &Map<K, V>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key.borrow()) == Some(value))
+    }
+}
+
+impl<K: Borrow<str>, V: Eq> Eq for Map<K, V> {}
+
+impl<K: Ord + Hash, V: Hash> Hash for Map<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for (key, value) in self.sorted() {
+            key.hash(state);
+            value.hash(state);
+        }
+    }
+}
+
+impl<K: Borrow<str> + Ord, V: Ord> PartialOrd for Map<K, V> {
+    fn partial_cmp(&self, other: &Map<K, V>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares the members sorted by key, as sequences of key-value pairs.
+impl<K: Borrow<str> + Ord, V: Ord> Ord for Map<K, V> {
+    fn cmp(&self, other: &Map<K, V>) -> Ordering {
+        self.sorted().cmp(&other.sorted())
+    }
+}
+
+impl<K: Borrow<str>, V> FromIterator<(K, V)> for Map<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Map<K, V> {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Borrow<str>, V> Extend<(K, V)> for Map<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Borrow<str>, V, const N: usize> From<[(K, V); N]> for Map<K, V> {
+    fn from(entries: [(K, V); N]) -> Map<K, V> {
+        Map::from_iter(entries)
+    }
+}
+
+/// An iterator over the members of a [`Map`] in insertion order.
+pub struct Iter<'a, K = String, V = JsonValue>(slice::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// A mutable iterator over the members of a [`Map`] in insertion order.
+pub struct IterMut<'a, K = String, V = JsonValue>(slice::IterMut<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+/// An owning iterator over the members of a [`Map`] in insertion order.
+pub struct IntoIter<K = String, V = JsonValue>(vec::IntoIter<(K, V)>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> IntoIterator for Map<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter(self.entries.into_iter())
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Map<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Map<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_of(keys: &[&str]) -> Map {
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), JsonValue::Number(i.into())))
+            .collect()
+    }
+
+    fn keys(map: &Map) -> Vec<&str> {
+        map.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn iterates_in_insertion_order() {
+        let map = map_of(&["zeta", "alpha", "mu", "beta"]);
+        assert_eq!(keys(&map), ["zeta", "alpha", "mu", "beta"]);
+        assert_eq!(map.len(), 4);
+
+        let values: Vec<f64> = map.values().filter_map(JsonValue::as_f64).collect();
+        assert_eq!(values, [0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn insert_existing_key_keeps_position() {
+        let mut map = map_of(&["a", "b", "c"]);
+        let old = map.insert("b".to_string(), JsonValue::Null);
+        assert_eq!(old, Some(JsonValue::Number(1.into())));
+        assert_eq!(keys(&map), ["a", "b", "c"]);
+        assert_eq!(map.get("b"), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    fn remove_keeps_order_of_the_rest() {
+        let mut map = map_of(&["a", "b", "c", "d"]);
+        assert_eq!(map.remove("b"), Some(JsonValue::Number(1.into())));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(keys(&map), ["a", "c", "d"]);
+
+        // The index still points at the shifted members.
+        assert_eq!(map.get("c"), Some(&JsonValue::Number(2.into())));
+        assert_eq!(map.get("d"), Some(&JsonValue::Number(3.into())));
+
+        map.insert("b".to_string(), JsonValue::Null);
+        assert_eq!(keys(&map), ["a", "c", "d", "b"]);
+        assert!(map.contains_key("b"));
+    }
+
+    #[test]
+    fn lookups_after_removals() {
+        let names: Vec<String> = (0..300).map(|i| format!("key{i}")).collect();
+        let mut map: Map<String, usize> = names.iter().cloned().zip(0..).collect();
+
+        for (i, name) in names.iter().enumerate() {
+            match i % 3 {
+                0 => assert_eq!(map.remove(name), Some(i)),
+                1 if i % 2 == 0 => {
+                    let Entry::Occupied(entry) = map.entry(name.clone()) else {
+                        panic!("expected `{name}` to be present");
+                    };
+                    assert_eq!(entry.remove(), i);
+                }
+                _ => {}
+            }
+        }
+
+        let kept = |i: usize| i % 3 == 2 || (i % 3 == 1 && i % 2 == 1);
+        for (i, name) in names.iter().enumerate() {
+            assert_eq!(map.get(name), kept(i).then_some(&i), "{name}");
+            assert_eq!(map.contains_key(name), kept(i));
+        }
+        let expected: Vec<usize> = (0..300).filter(|&i| kept(i)).collect();
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), expected);
+
+        // Removed keys can be inserted again, at the end.
+        assert_eq!(map.insert("key0".to_string(), 1000), None);
+        assert_eq!(map.get("key0"), Some(&1000));
+        assert_eq!(map.keys().last().map(String::as_str), Some("key0"));
+        assert_eq!(map.len(), expected.len() + 1);
+    }
+
+    #[test]
+    fn get_mut_and_iter_mut() {
+        let mut map = map_of(&["a", "b"]);
+        *map.get_mut("a").unwrap() = JsonValue::Boolean(true);
+        for (key, value) in &mut map {
+            if key == "b" {
+                *value = JsonValue::Null;
+            }
+        }
+        assert_eq!(map.get("a"), Some(&JsonValue::Boolean(true)));
+        assert_eq!(map.get("b"), Some(&JsonValue::Null));
+        assert!(map.get_mut("missing").is_none());
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let forward = Map::from([
+            ("a".to_string(), JsonValue::Null),
+            ("b".to_string(), JsonValue::Boolean(true)),
+        ]);
+        let backward = Map::from([
+            ("b".to_string(), JsonValue::Boolean(true)),
+            ("a".to_string(), JsonValue::Null),
+        ]);
+        assert_eq!(forward, backward);
+        assert_ne!(forward, map_of(&["a", "b"]));
+        assert_ne!(forward, map_of(&["a"]));
+    }
+
+    #[test]
+    fn entry_inserts_updates_and_removes() {
+        let mut map = map_of(&["a", "b"]);
+
+        *map.entry("c".to_string()).or_insert(JsonValue::Null) = JsonValue::Boolean(true);
+        map.entry("a".to_string())
+            .and_modify(|value| *value = JsonValue::Null)
+            .or_insert_with(|| unreachable!());
+        assert_eq!(keys(&map), ["a", "b", "c"]);
+        assert_eq!(map.get("a"), Some(&JsonValue::Null));
+        assert_eq!(map.get("c"), Some(&JsonValue::Boolean(true)));
+
+        let Entry::Occupied(mut entry) = map.entry("b".to_string()) else {
+            panic!("expected `b` to be present");
+        };
+        assert_eq!(entry.key(), "b");
+        assert_eq!(entry.insert(JsonValue::Null), JsonValue::Number(1.into()));
+        assert_eq!(entry.remove(), JsonValue::Null);
+
+        assert_eq!(keys(&map), ["a", "c"]);
+        assert_eq!(map.get("c"), Some(&JsonValue::Boolean(true)));
+        assert!(matches!(map.entry("b".to_string()), Entry::Vacant(_)));
+    }
+
+    #[test]
+    fn hash_and_order_ignore_insertion_order() {
+        use std::hash::DefaultHasher;
+
+        let hash_of = |map: &Map| {
+            let mut hasher = DefaultHasher::new();
+            map.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let forward = map_of(&["a", "b"]);
+        let mut backward = Map::new();
+        backward.insert("b".to_string(), JsonValue::Number(1.into()));
+        backward.insert("a".to_string(), JsonValue::Number(0.into()));
+        assert_eq!(hash_of(&forward), hash_of(&backward));
+        assert_eq!(forward.cmp(&backward), Ordering::Equal);
+
+        assert!(map_of(&["a"]) < map_of(&["a", "b"]));
+        assert!(map_of(&["a", "b"]) < map_of(&["b"]));
+        assert_eq!(forward.clone(), forward);
+    }
+
+    #[test]
+    fn into_iter_and_clear() {
+        let map = map_of(&["x", "y"]);
+        let entries: Vec<(String, JsonValue)> = map.into_iter().rev().collect();
+        assert_eq!(entries[0].0, "y");
+
+        let mut map = map_of(&["x", "y"]);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get("x"), None);
+    }
+}
@@ -0,0 +1,471 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// A JSON number. Integer literals are kept exactly as `u64` or `i64` when
+/// they fit, anything else as `f64`. With [`ParseOptions::raw_numbers`] the
+/// literal text is kept as well, so numbers beyond those types still print
+/// back exactly as they were read.
+///
+/// Numbers are equal when they are stored the same way with the same value
+/// and raw text, so `1` and `1.0` differ. Floats compare bit for bit: `0.0`
+/// and `-0.0` differ, and NaN, which is stored as a single canonical NaN,
+/// equals itself. This makes `Number` `Eq` and `Hash`, and [`Ord`] orders
+/// numbers by value, placing NaN above every other number.
+///
+/// [`ParseOptions::raw_numbers`]: crate::ParseOptions::raw_numbers
+#[derive(Clone)]
+pub struct Number {
+    value: N,
+    raw: Option<Box<str>>,
+}
+
+// Negative integers are always `NegInt`, so each integer has one
+// representation.
+#[derive(Debug, Clone, Copy)]
+enum N {
+    PosInt(u64),
+    NegInt(i64),
+    Float(f64),
+}
+
+impl N {
+    // Orders by value, with `f64::total_cmp` between floats. An integer and a
+    // float of the same value are told apart by `Number::cmp`.
+    fn cmp_value(&self, other: &N) -> Ordering {
+        match (*self, *other) {
+            (N::PosInt(a), N::PosInt(b)) => a.cmp(&b),
+            (N::NegInt(a), N::NegInt(b)) => a.cmp(&b),
+            (N::NegInt(_), N::PosInt(_)) => Ordering::Less,
+            (N::PosInt(_), N::NegInt(_)) => Ordering::Greater,
+            (N::Float(a), N::Float(b)) => a.total_cmp(&b),
+            (N::PosInt(a), N::Float(b)) => Self::cmp_int_float(i128::from(a), b),
+            (N::NegInt(a), N::Float(b)) => Self::cmp_int_float(i128::from(a), b),
+            (N::Float(a), N::PosInt(b)) => Self::cmp_int_float(i128::from(b), a).reverse(),
+            (N::Float(a), N::NegInt(b)) => Self::cmp_int_float(i128::from(b), a).reverse(),
+        }
+    }
+
+    // Compares exactly, without rounding the integer to an `f64`.
+    fn cmp_int_float(int: i128, float: f64) -> Ordering {
+        if float.is_nan() || float >= 2f64.powi(64) {
+            return Ordering::Less;
+        }
+        if float < -(2f64.powi(63)) {
+            return Ordering::Greater;
+        }
+
+        // The float is now within the integer range, where its integral part
+        // converts exactly.
+        let whole = float.trunc();
+        int.cmp(&(whole as i128)).then_with(|| {
+            0f64.partial_cmp(&(float - whole))
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, N::Float(_))
+    }
+}
+
+impl Number {
+    // Converts a literal the lexer accepted. "-0" is kept as a float so its
+    // sign survives, and integers too large for 64 bits fall back to `f64`.
+    // Literals beyond the range of an `f64`, such as `1e400`, are rejected
+    // rather than read as infinity, which JSON cannot write back.
+    pub(crate) fn from_literal(text: &str, keep_raw: bool) -> Option<Number> {
+        let integral = !text.contains(['.', 'e', 'E']);
+        let float = || {
+            text.parse()
+                .ok()
+                .filter(|n: &f64| n.is_finite())
+                .map(N::Float)
+        };
+
+        let value = match (integral, text.strip_prefix('-')) {
+            (false, _) | (true, Some("0")) => float()?,
+            (true, Some(_)) => text.parse().ok().map(N::NegInt).or_else(float)?,
+            (true, None) => text.parse().ok().map(N::PosInt).or_else(float)?,
+        };
+
+        Some(Number {
+            value,
+            raw: keep_raw.then(|| text.into()),
+        })
+    }
+
+    /// The number as an `i64`, if it is an integer in range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.value {
+            N::PosInt(n) => i64::try_from(n).ok(),
+            N::NegInt(n) => Some(n),
+            N::Float(_) => None,
+        }
+    }
+
+    /// The number as a `u64`, if it is a non-negative integer in range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.value {
+            N::PosInt(n) => Some(n),
+            N::NegInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// The number as an `f64`, rounded if it cannot be represented exactly.
+    pub fn as_f64(&self) -> f64 {
+        match self.value {
+            N::PosInt(n) => n as f64,
+            N::NegInt(n) => n as f64,
+            N::Float(n) => n,
+        }
+    }
+
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Whether the number is stored as an `f64`, which is the case for any
+    /// literal with a fraction or exponent and for integers out of range.
+    pub fn is_f64(&self) -> bool {
+        matches!(self.value, N::Float(_))
+    }
+
+    // An integer too wide for 64 bits, kept as an `f64` and its exact digits.
+    fn wide(value: f64, text: String) -> Number {
+        Number {
+            value: N::Float(value),
+            raw: Some(text.into()),
+        }
+    }
+
+    /// The literal the number was parsed from, if it was kept.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    // The value of an integer too wide for 64 bits, read from its raw text.
+    // Narrower integers are stored as such, except `-0`, which is a float.
+    pub(crate) fn wide_integer<T: FromStr + Default + PartialEq>(&self) -> Option<T> {
+        self.raw()?.parse().ok().filter(|n| *n != T::default())
+    }
+}
+
+/// Writes the literal the number was read from when it was kept. Otherwise
+/// integers are written exactly and floats in the shortest form that reads
+/// back as the same `f64`, keeping a fraction or exponent so they stay floats.
+/// Infinite and NaN values, which JSON cannot represent and which only come
+/// from converting an `f64`, are written as `null`.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(raw) = &self.raw {
+            return f.write_str(raw);
+        }
+
+        match self.value {
+            N::PosInt(n) => write!(f, "{n}"),
+            N::NegInt(n) => write!(f, "{n}"),
+            N::Float(n) if n.is_finite() => write!(f, "{n:?}"),
+            N::Float(_) => f.write_str("null"),
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.value {
+            N::PosInt(n) => (0u8, n).hash(state),
+            N::NegInt(n) => (1u8, n).hash(state),
+            N::Float(n) => (2u8, n.to_bits()).hash(state),
+        }
+        self.raw.hash(state);
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders numbers by value. An integer comes before a float of the same
+/// value, and numbers that differ only in their raw text are ordered by it.
+impl Ord for Number {
+    fn cmp(&self, other: &Number) -> Ordering {
+        self.value
+            .cmp_value(&other.value)
+            .then_with(|| self.value.is_float().cmp(&other.value.is_float()))
+            .then_with(|| self.raw.cmp(&other.raw))
+    }
+}
+
+impl fmt::Debug for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Number({self})")
+    }
+}
+
+impl From<u64> for Number {
+    fn from(n: u64) -> Number {
+        Number {
+            value: N::PosInt(n),
+            raw: None,
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(n: i64) -> Number {
+        let value = match u64::try_from(n) {
+            Ok(n) => N::PosInt(n),
+            Err(_) => N::NegInt(n),
+        };
+
+        Number { value, raw: None }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(n: f64) -> Number {
+        // Every NaN is stored with the same bits so that NaNs are equal.
+        let n = if n.is_nan() { f64::NAN } else { n };
+        Number {
+            value: N::Float(n),
+            raw: None,
+        }
+    }
+}
+
+macro_rules! from_integer {
+    ($wide:ty => $($ty:ty),*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(n: $ty) -> Number {
+                    Number::from(n as $wide)
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(u64 => u8, u16, u32, usize);
+from_integer!(i64 => i8, i16, i32, isize);
+
+/// Converts exactly when the value fits in an `i64` or `u64`. Wider values are
+/// stored as the nearest `f64` and keep their digits as the raw text, as when
+/// parsing them with [`ParseOptions::raw_numbers`](crate::ParseOptions::raw_numbers),
+/// so they still print exactly.
+impl From<i128> for Number {
+    fn from(n: i128) -> Number {
+        match i64::try_from(n) {
+            Ok(n) => Number::from(n),
+            Err(_) => match u64::try_from(n) {
+                Ok(n) => Number::from(n),
+                Err(_) => Number::wide(n as f64, n.to_string()),
+            },
+        }
+    }
+}
+
+/// Converts like the `From<i128>` impl.
+impl From<u128> for Number {
+    fn from(n: u128) -> Number {
+        match u64::try_from(n) {
+            Ok(n) => Number::from(n),
+            Err(_) => Number::wide(n as f64, n.to_string()),
+        }
+    }
+}
+
+impl From<f32> for Number {
+    fn from(n: f32) -> Number {
+        Number::from(f64::from(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str) -> Number {
+        Number::from_literal(text, false).expect("should convert")
+    }
+
+    #[test]
+    fn integers_are_exact() {
+        assert_eq!(number("9007199254740993").as_u64(), Some(9007199254740993));
+        assert_eq!(number("18446744073709551615").as_u64(), Some(u64::MAX));
+        assert_eq!(number("-9223372036854775808").as_i64(), Some(i64::MIN));
+        assert_eq!(number("-1").as_u64(), None);
+        assert_eq!(number("18446744073709551615").as_i64(), None);
+        assert!(number("0").is_u64() && number("0").is_i64());
+    }
+
+    #[test]
+    fn fractions_and_large_integers_are_floats() {
+        assert!(number("1.0").is_f64());
+        assert!(number("1e2").is_f64());
+        assert_eq!(number("1e2").as_i64(), None);
+        assert!(number("18446744073709551616").is_f64());
+        assert!(number("-9223372036854775809").is_f64());
+        assert_eq!(number("2.5").as_f64(), 2.5);
+        assert_eq!(number("-7").as_f64(), -7.0);
+    }
+
+    #[test]
+    fn negative_zero_keeps_its_sign() {
+        let zero = number("-0");
+        assert!(zero.is_f64());
+        assert!(zero.as_f64().is_sign_negative());
+        assert_eq!(zero.to_string(), "-0.0");
+    }
+
+    #[test]
+    fn display_writes_numbers_back() {
+        for text in [
+            "0",
+            "42",
+            "-42",
+            "9007199254740993",
+            "18446744073709551615",
+            "0.1",
+        ] {
+            assert_eq!(number(text).to_string(), text);
+        }
+        assert_eq!(number("1.50").to_string(), "1.5");
+        assert_eq!(number("1E2").to_string(), "100.0");
+        assert_eq!(number("1e300").to_string(), "1e300");
+        assert_eq!(number("1e-400").to_string(), "0.0");
+    }
+
+    #[test]
+    fn out_of_range_literals_are_rejected() {
+        for text in ["1e400", "-1e400", "1.8e308", &"9".repeat(400)] {
+            assert!(Number::from_literal(text, false).is_none(), "{text}");
+            assert!(Number::from_literal(text, true).is_none(), "{text}");
+        }
+        assert!(Number::from_literal("1.7e308", false).is_some());
+    }
+
+    #[test]
+    fn raw_literals_are_written_as_read() {
+        for text in [
+            "1.50",
+            "1E2",
+            "-0",
+            "123456789012345678901234567890",
+            "1e-400",
+        ] {
+            let number = Number::from_literal(text, true).unwrap();
+            assert_eq!(number.raw(), Some(text));
+            assert_eq!(number.to_string(), text);
+        }
+        assert_eq!(number("1.50").raw(), None);
+    }
+
+    #[test]
+    fn from_primitives() {
+        assert_eq!(Number::from(5u8), number("5"));
+        assert_eq!(Number::from(5i32), number("5"));
+        assert_eq!(Number::from(-5i64), number("-5"));
+        assert_eq!(Number::from(usize::MAX).as_u64(), Some(usize::MAX as u64));
+        assert_eq!(Number::from(0.5f32), number("0.5"));
+        assert_eq!(Number::from(f64::NAN).to_string(), "null");
+    }
+
+    #[test]
+    fn from_wide_integers() {
+        assert_eq!(Number::from(-5i128), number("-5"));
+        assert_eq!(Number::from(u128::from(u64::MAX)).as_u64(), Some(u64::MAX));
+        assert_eq!(Number::from(i128::from(i64::MIN)).as_i64(), Some(i64::MIN));
+
+        let big = Number::from(u128::MAX);
+        assert!(big.is_f64());
+        assert_eq!(big.to_string(), u128::MAX.to_string());
+        assert_eq!(big.as_f64(), u128::MAX as f64);
+        assert_eq!(
+            big,
+            Number::from_literal(&u128::MAX.to_string(), true).unwrap()
+        );
+
+        let small = Number::from(i128::MIN);
+        assert_eq!(small.raw(), Some(i128::MIN.to_string().as_str()));
+        assert_eq!(small.as_f64(), i128::MIN as f64);
+    }
+
+    fn hash_of(number: &Number) -> u64 {
+        use std::hash::DefaultHasher;
+
+        let mut hasher = DefaultHasher::new();
+        number.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equality_is_bit_exact() {
+        let nan = Number::from(f64::NAN);
+        assert_eq!(nan, Number::from(-f64::NAN));
+        assert_eq!(
+            hash_of(&nan),
+            hash_of(&Number::from(f64::from_bits(0x7ff8_0000_0000_0001)))
+        );
+
+        assert_ne!(number("0.0"), number("-0"));
+        assert_ne!(number("1"), number("1.0"));
+        assert_eq!(number("1.0"), number("1.00"));
+        assert_ne!(
+            Number::from_literal("1.0", true),
+            Number::from_literal("1.00", true)
+        );
+        assert_eq!(hash_of(&number("1.0")), hash_of(&number("1e0")));
+        assert_ne!(hash_of(&number("1")), hash_of(&number("1.0")));
+    }
+
+    #[test]
+    fn ordering_is_numeric_and_total() {
+        let sorted = [
+            number("-1e300"),
+            number("-9223372036854775808"),
+            number("-1.5"),
+            number("-1"),
+            number("0"),
+            number("-0"),
+            number("0.0"),
+            number("0.5"),
+            number("1"),
+            number("1.0"),
+            number("9007199254740993"),
+            number("18446744073709551615"),
+            number("18446744073709551616"),
+            number("1e300"),
+            Number::from(f64::NAN),
+        ];
+        for pair in sorted.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+        }
+
+        // Integers above 2^53 still compare exactly against floats.
+        assert!(number("9007199254740993") > number("9007199254740992.0"));
+        assert!(number("9007199254740991") < number("9007199254740992.0"));
+
+        let mut shuffled = sorted.clone();
+        shuffled.reverse();
+        shuffled.sort();
+        assert_eq!(shuffled, sorted);
+    }
+}
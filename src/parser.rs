@@ -1,231 +1,930 @@
 use crate::{
     lexer::{Lexer, Token, TokenKind},
-    JsonError, JsonValue,
+    Diagnostic, DuplicateKeys, JsonError, JsonValue, JsonValueRef, Map, Number, ParseOptions, Span,
 };
-use std::collections::HashMap;
+use std::{
+    borrow::{Borrow, Cow},
+    collections::HashSet,
+    hash::Hash,
+};
+
+// A value the parser can build. `JsonValue` copies every string out of the
+// input, while `JsonValueRef` borrows those that have no escapes to decode.
+pub(crate) trait Value<'a>: Sized {
+    type Key: Borrow<str> + Hash + Eq + Clone;
+
+    fn key(text: Cow<'a, str>) -> Self::Key;
+    fn null() -> Self;
+    fn boolean(value: bool) -> Self;
+    fn number(number: Number) -> Self;
+    fn string(text: Cow<'a, str>) -> Self;
+    fn array(values: Vec<Self>) -> Self;
+    fn object(object: Map<Self::Key, Self>) -> Self;
+    fn as_array_mut(&mut self) -> Option<&mut Vec<Self>>;
+}
+
+impl<'a> Value<'a> for JsonValue {
+    type Key = String;
+
+    fn key(text: Cow<'a, str>) -> String {
+        text.into_owned()
+    }
+
+    fn null() -> JsonValue {
+        JsonValue::Null
+    }
+
+    fn boolean(value: bool) -> JsonValue {
+        JsonValue::Boolean(value)
+    }
+
+    fn number(number: Number) -> JsonValue {
+        JsonValue::Number(number)
+    }
+
+    fn string(text: Cow<'a, str>) -> JsonValue {
+        JsonValue::String(text.into_owned())
+    }
+
+    fn array(values: Vec<JsonValue>) -> JsonValue {
+        JsonValue::Array(values)
+    }
+
+    fn object(object: Map) -> JsonValue {
+        JsonValue::Object(object)
+    }
+
+    fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Value<'a> for JsonValueRef<'a> {
+    type Key = Cow<'a, str>;
+
+    fn key(text: Cow<'a, str>) -> Cow<'a, str> {
+        text
+    }
+
+    fn null() -> JsonValueRef<'a> {
+        JsonValueRef::Null
+    }
+
+    fn boolean(value: bool) -> JsonValueRef<'a> {
+        JsonValueRef::Boolean(value)
+    }
+
+    fn number(number: Number) -> JsonValueRef<'a> {
+        JsonValueRef::Number(number)
+    }
+
+    fn string(text: Cow<'a, str>) -> JsonValueRef<'a> {
+        JsonValueRef::String(text)
+    }
+
+    fn array(values: Vec<JsonValueRef<'a>>) -> JsonValueRef<'a> {
+        JsonValueRef::Array(values)
+    }
+
+    fn object(object: Map<Cow<'a, str>, JsonValueRef<'a>>) -> JsonValueRef<'a> {
+        JsonValueRef::Object(object)
+    }
+
+    fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValueRef<'a>>> {
+        match self {
+            JsonValueRef::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct Parser<'a> {
-    input: &'a [u8],
-    lexer: Lexer,
+    pub(crate) input: &'a [u8],
+    pub(crate) lexer: Lexer,
+    pub(crate) options: ParseOptions,
+}
+
+// An array or object whose closing bracket has not been read yet.
+enum Frame<K, V> {
+    Array(Vec<V>),
+    // `key` names the member whose value is being parsed and `collected` the
+    // keys whose values `DuplicateKeys::Collect` has gathered into an array.
+    Object {
+        object: Map<K, V>,
+        key: K,
+        collected: HashSet<K>,
+    },
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(input: &'a [u8], lexer: Lexer) -> Parser<'a> {
-        Parser { input, lexer }
+    pub fn new(input: &'a [u8], lexer: Lexer, options: &ParseOptions) -> Parser<'a> {
+        Parser {
+            input,
+            lexer,
+            options: options.clone(),
+        }
     }
 
-    fn resolve_number(num_str: &[u8]) -> Result<f64, JsonError> {
-        let num: f64 = std::str::from_utf8(num_str)?.parse()?;
-        Ok(num)
+    // The lexer only emits number tokens that match the JSON grammar, of which
+    // `Number::from_literal` only rejects those out of the range of an `f64`.
+    pub(crate) fn resolve_number(token: &Token, parser: &Parser<'a>) -> Result<Number, JsonError> {
+        let text = Self::resolve_string(token, parser.input);
+        Number::from_literal(text, parser.options.raw_numbers).ok_or(JsonError::InvalidNumber {
+            span: parser.lexer.last_token_span(),
+        })
     }
 
-    fn resolve_string(token: &Token, input: &'a [u8]) -> &'a str {
-        let start = token.start as usize;
-        let end = token.end as usize;
+    pub(crate) fn resolve_string(token: &Token, input: &'a [u8]) -> &'a str {
+        let start = token.start;
+        let end = token.end;
 
-        unsafe { std::str::from_utf8_unchecked(&input[start as usize..end as usize]) }
+        // SAFETY: the lexer validates the UTF-8 of every string token and number
+        // tokens only contain ASCII, so any token span is valid UTF-8.
+        unsafe { std::str::from_utf8_unchecked(&input[start..end]) }
     }
 
-    fn expect_colon(parser: &mut Parser<'a>) -> Result<(), JsonError> {
-        let token = parser
-            .lexer
-            .next_token(parser.input)
-            .ok_or(JsonError::UnexpectedEof)?;
+    fn read_hex4(raw: &[u8], pos: usize) -> Option<u32> {
+        let digits = raw.get(pos..pos + 4)?;
+        let mut code: u32 = 0;
+        for digit in digits {
+            code = (code << 4) | (*digit as char).to_digit(16)?;
+        }
+        Some(code)
+    }
 
-        match token.kind {
-            TokenKind::Colon => Ok(()),
-            _ => Err(JsonError::UnexpectedToken {
-                line: parser.lexer.last_token_line as usize,
-                col: parser.lexer.last_token_col as usize,
+    // Decodes the escape sequences of a string token, borrowing the input when
+    // there are none. Must be called while the token is still the lexer's last
+    // token so errors can be positioned.
+    pub(crate) fn decode_string(
+        token: &Token,
+        parser: &Parser<'a>,
+    ) -> Result<Cow<'a, str>, JsonError> {
+        let text = Self::resolve_string(token, parser.input);
+        let raw = text.as_bytes();
+        if !raw.contains(&b'\\') {
+            return Ok(Cow::Borrowed(text));
+        }
+
+        // The token starts one byte and one column after its opening quote.
+        let span_at = |offset: usize| Span {
+            offset: token.start + offset,
+            line: parser.lexer.last_token_line,
+            col: parser.lexer.last_token_col
+                + 1
+                + parser.lexer.column_unit.measure(&text[..offset]),
+        };
+
+        // Unescaped runs are copied as they are. Backslashes are ASCII, so the
+        // run boundaries always fall on character boundaries.
+        let mut decoded = String::with_capacity(raw.len());
+        let mut run_start = 0;
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] != b'\\' {
+                i += 1;
+                continue;
+            }
+
+            decoded.push_str(&text[run_start..i]);
+            let escape_start = i;
+            let ch = match raw.get(i + 1) {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'/') => '/',
+                Some(b'b') => '\u{8}',
+                Some(b'f') => '\u{c}',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'u') => {
+                    let high = Self::read_hex4(raw, i + 2).ok_or(JsonError::InvalidEscape {
+                        span: span_at(escape_start),
+                    })?;
+                    i += 4;
+
+                    let code = match high {
+                        0xD800..=0xDBFF => {
+                            if raw.get(i + 2) != Some(&b'\\') || raw.get(i + 3) != Some(&b'u') {
+                                return Err(JsonError::LoneSurrogate {
+                                    span: span_at(escape_start),
+                                });
+                            }
+                            let low =
+                                Self::read_hex4(raw, i + 4).ok_or(JsonError::InvalidEscape {
+                                    span: span_at(i + 2),
+                                })?;
+                            if !(0xDC00..=0xDFFF).contains(&low) {
+                                return Err(JsonError::LoneSurrogate {
+                                    span: span_at(escape_start),
+                                });
+                            }
+                            i += 6;
+                            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                        }
+                        0xDC00..=0xDFFF => {
+                            return Err(JsonError::LoneSurrogate {
+                                span: span_at(escape_start),
+                            });
+                        }
+                        _ => high,
+                    };
+
+                    char::from_u32(code).ok_or(JsonError::InvalidEscape {
+                        span: span_at(escape_start),
+                    })?
+                }
+                _ => {
+                    return Err(JsonError::InvalidEscape {
+                        span: span_at(escape_start),
+                    });
+                }
+            };
+
+            decoded.push(ch);
+            i += 2;
+            run_start = i;
+        }
+
+        decoded.push_str(&text[run_start..]);
+        Ok(Cow::Owned(decoded))
+    }
+
+    // Reads the next token, where `expected` describes what may follow.
+    pub(crate) fn next_token(
+        parser: &mut Parser<'a>,
+        expected: &'static str,
+    ) -> Result<Token, JsonError> {
+        match parser.lexer.next_token(parser.input)? {
+            Some(token) => Ok(token),
+            None => Err(JsonError::UnexpectedEof {
+                expected,
+                span: parser.lexer.span(),
             }),
         }
     }
 
-    fn parse_string(token: &Token, parser: &mut Parser<'a>) -> Result<JsonValue, JsonError> {
+    // Must be called while `token` is still the lexer's last token.
+    pub(crate) fn unexpected(
+        token: &Token,
+        parser: &Parser<'a>,
+        expected: &'static str,
+    ) -> JsonError {
+        JsonError::UnexpectedToken {
+            found: token.kind.describe(),
+            expected,
+            span: parser.lexer.last_token_span(),
+        }
+    }
+
+    pub(crate) fn expect_colon(parser: &mut Parser<'a>) -> Result<(), JsonError> {
+        let token = Self::next_token(parser, "':'")?;
+
         match token.kind {
-            TokenKind::String => {
-                let start = token.start as usize;
-                let end = token.end as usize;
-
-                Ok(JsonValue::String(
-                    std::str::from_utf8(&parser.input[start as usize..end as usize])
-                        .unwrap()
-                        .to_string(),
-                ))
-            }
-            _ => Err(JsonError::UnexpectedToken {
-                line: parser.lexer.last_token_line as usize,
-                col: parser.lexer.last_token_col as usize,
-            }),
+            TokenKind::Colon => Ok(()),
+            _ => Err(Self::unexpected(&token, parser, "':'")),
         }
     }
 
-    fn parse_number(token: &Token, parser: &mut Parser<'a>) -> Result<JsonValue, JsonError> {
+    // Builds the scalar that starts with `token`. `expected` describes what the
+    // caller accepts at this point and is used when `token` cannot start a value.
+    fn parse_scalar<V: Value<'a>>(
+        token: &Token,
+        parser: &mut Parser<'a>,
+        expected: &'static str,
+    ) -> Result<V, JsonError> {
         match token.kind {
-            TokenKind::Number => {
-                let start = token.start as usize;
-                let end = token.end as usize;
+            TokenKind::Null => Ok(V::null()),
+            TokenKind::True => Ok(V::boolean(true)),
+            TokenKind::False => Ok(V::boolean(false)),
+            TokenKind::Number => Ok(V::number(Self::resolve_number(token, parser)?)),
+            TokenKind::String => Ok(V::string(Self::decode_string(token, parser)?)),
+            _ => Err(Self::unexpected(token, parser, expected)),
+        }
+    }
 
-                Ok(JsonValue::Number(Self::resolve_number(
-                    &parser.input[start..end],
-                )?))
+    // Reads the `"key":` of an object member starting at `token`.
+    fn parse_key<V: Value<'a>>(
+        token: &Token,
+        parser: &mut Parser<'a>,
+        object: &Map<V::Key, V>,
+        expected: &'static str,
+    ) -> Result<V::Key, JsonError> {
+        let TokenKind::String = token.kind else {
+            return Err(Self::unexpected(token, parser, expected));
+        };
+
+        let key = Self::decode_string(token, parser)?;
+        if parser.options.duplicate_keys == DuplicateKeys::Error && object.contains_key(&key) {
+            return Err(JsonError::DuplicateKey {
+                key: key.into_owned(),
+                span: parser.lexer.last_token_span(),
+            });
+        }
+
+        Self::expect_colon(parser)?;
+        Ok(V::key(key))
+    }
+
+    // Adds a member to `object`, settling a repeated key by the duplicate key
+    // policy. Under `DuplicateKeys::Error` the key has been reported already
+    // and the later value replaces the earlier one.
+    fn insert_member<V: Value<'a>>(
+        parser: &Parser<'a>,
+        object: &mut Map<V::Key, V>,
+        collected: &mut HashSet<V::Key>,
+        key: V::Key,
+        value: V,
+    ) {
+        match parser.options.duplicate_keys {
+            DuplicateKeys::Error | DuplicateKeys::LastWins => {
+                object.insert(key, value);
+            }
+            DuplicateKeys::FirstWins => {
+                if !object.contains_key(key.borrow()) {
+                    object.insert(key, value);
+                }
+            }
+            DuplicateKeys::Collect => {
+                let Some(existing) = object.get_mut(key.borrow()) else {
+                    object.insert(key, value);
+                    return;
+                };
+
+                let values = if collected.contains(key.borrow()) {
+                    existing.as_array_mut()
+                } else {
+                    None
+                };
+                if let Some(values) = values {
+                    values.push(value);
+                    return;
+                }
+
+                let first = std::mem::replace(existing, V::null());
+                *existing = V::array(vec![first, value]);
+                collected.insert(key);
             }
-            _ => Err(JsonError::UnexpectedToken {
-                line: parser.lexer.last_token_line as usize,
-                col: parser.lexer.last_token_col as usize,
-            }),
         }
     }
 
-    fn parse_boolean(token: &Token, parser: &mut Parser<'a>) -> Result<JsonValue, JsonError> {
-        match token.kind {
-            TokenKind::True => Ok(JsonValue::Boolean(true)),
-            TokenKind::False => Ok(JsonValue::Boolean(false)),
-            _ => Err(JsonError::UnexpectedToken {
-                line: parser.lexer.last_token_line as usize,
-                col: parser.lexer.last_token_col as usize,
-            }),
+    // Called on an opening bracket while `depth` containers are already open.
+    pub(crate) fn check_depth(parser: &Parser<'a>, depth: usize) -> Result<(), JsonError> {
+        if depth >= parser.options.max_depth {
+            return Err(JsonError::DepthLimitExceeded {
+                limit: parser.options.max_depth,
+                span: parser.lexer.last_token_span(),
+            });
         }
+
+        Ok(())
     }
 
-    fn parse_array(parser: &mut Parser<'a>) -> Result<JsonValue, JsonError> {
-        let mut values: Vec<JsonValue> = Vec::new();
-        let mut last_seen_token: Option<TokenKind> = None;
+    // Open arrays and objects are kept on `stack` rather than the call stack,
+    // so nesting depth is bounded by `max_depth` and not by the thread's stack.
+    //
+    // Values and separators must alternate: after each complete value exactly
+    // one ',' or closing bracket has to follow, and object members are a
+    // string key, a ':' and a value.
+    fn parse_root<V: Value<'a>>(&mut self) -> Result<V, JsonError> {
+        let mut stack: Vec<Frame<V::Key, V>> = Vec::new();
+        let mut token = Self::next_token(self, "a value")?;
+        let mut expected = "a value";
+
         loop {
-            let token = parser
-                .lexer
-                .next_token(parser.input)
-                .ok_or(JsonError::UnexpectedEof)?;
+            let mut value = match token.kind {
+                TokenKind::LeftBracket => {
+                    Self::check_depth(self, stack.len())?;
 
-            match token.kind {
-                TokenKind::Null => values.push(JsonValue::Null),
-                TokenKind::True => values.push(JsonValue::Boolean(true)),
-                TokenKind::False => values.push(JsonValue::Boolean(false)),
-                TokenKind::Number => {
-                    let start = token.start as usize;
-                    let end = token.end as usize;
-                    values.push(JsonValue::Number(Self::resolve_number(
-                        &parser.input[start..end],
-                    )?))
-                }
-                TokenKind::String => {
-                    let start = token.start as usize;
-                    let end = token.end as usize;
-                    values.push(JsonValue::String(
-                        std::str::from_utf8(&parser.input[start as usize..end as usize])
-                            .unwrap()
-                            .to_string(),
-                    ))
+                    token = Self::next_token(self, "a value or ']'")?;
+                    if let TokenKind::RightBracket = token.kind {
+                        V::array(Vec::new())
+                    } else {
+                        stack.push(Frame::Array(Vec::new()));
+                        expected = "a value or ']'";
+                        continue;
+                    }
                 }
-                TokenKind::LeftBrace => values.push(Self::parse_object(parser)?),
-                TokenKind::LeftBracket => values.push(Self::parse_array(parser)?),
-                TokenKind::Comma => {
-                    if let Some(TokenKind::Comma) = last_seen_token {
-                        return Err(JsonError::UnexpectedToken {
-                            line: parser.lexer.last_token_line as usize,
-                            col: parser.lexer.last_token_col as usize,
+                TokenKind::LeftBrace => {
+                    Self::check_depth(self, stack.len())?;
+
+                    let key_token = Self::next_token(self, "a string key or '}'")?;
+                    if let TokenKind::RightBrace = key_token.kind {
+                        V::object(Map::new())
+                    } else {
+                        let object = Map::new();
+                        let key =
+                            Self::parse_key(&key_token, self, &object, "a string key or '}'")?;
+                        stack.push(Frame::Object {
+                            object,
+                            key,
+                            collected: HashSet::new(),
                         });
+
+                        token = Self::next_token(self, "a value")?;
+                        expected = "a value";
+                        continue;
                     }
                 }
-                TokenKind::RightBracket => break,
-                _ => {
-                    return Err(JsonError::UnexpectedToken {
-                        line: parser.lexer.last_token_line as usize,
-                        col: parser.lexer.last_token_col as usize,
-                    })
-                }
+                _ => Self::parse_scalar(&token, self, expected)?,
+            };
+
+            // `value` is complete. Add it to the innermost open container and
+            // close containers until one continues with a ','.
+            loop {
+                let Some(frame) = stack.pop() else {
+                    return Ok(value);
+                };
+
+                value = match frame {
+                    Frame::Array(mut values) => {
+                        values.push(value);
+
+                        let separator = Self::next_token(self, "',' or ']'")?;
+                        match separator.kind {
+                            TokenKind::Comma => {
+                                stack.push(Frame::Array(values));
+                                break;
+                            }
+                            TokenKind::RightBracket => V::array(values),
+                            _ => return Err(Self::unexpected(&separator, self, "',' or ']'")),
+                        }
+                    }
+                    Frame::Object {
+                        mut object,
+                        key,
+                        mut collected,
+                    } => {
+                        Self::insert_member(self, &mut object, &mut collected, key, value);
+
+                        let separator = Self::next_token(self, "',' or '}'")?;
+                        match separator.kind {
+                            TokenKind::Comma => {
+                                let key_token = Self::next_token(self, "a string key")?;
+                                let key =
+                                    Self::parse_key(&key_token, self, &object, "a string key")?;
+                                stack.push(Frame::Object {
+                                    object,
+                                    key,
+                                    collected,
+                                });
+                                break;
+                            }
+                            TokenKind::RightBrace => V::object(object),
+                            _ => return Err(Self::unexpected(&separator, self, "',' or '}'")),
+                        }
+                    }
+                };
+            }
+
+            token = Self::next_token(self, "a value")?;
+            expected = "a value";
+        }
+    }
+
+    pub fn parse<V: Value<'a>>(mut self) -> Result<V, JsonError> {
+        let value = self.parse_root()?;
+        self.finish()?;
+        Ok(value)
+    }
+
+    // Checks that only whitespace follows the value just read.
+    pub(crate) fn finish(&mut self) -> Result<(), JsonError> {
+        self.lexer.skip_whitespace(self.input);
+        if self.lexer.pos < self.input.len() {
+            return Err(JsonError::TrailingCharacters {
+                span: self.lexer.span(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Parses the first value and returns the number of bytes it spans,
+    // leaving anything after it unread.
+    pub fn parse_prefix<V: Value<'a>>(mut self) -> Result<(V, usize), JsonError> {
+        let value = self.parse_root()?;
+        Ok((value, self.lexer.pos))
+    }
+}
+
+// A token read while recovering from errors. Lexing errors are reported as
+// they are read and leave an `Invalid` token in place of what failed to lex.
+enum Lexed {
+    Token(Token),
+    Invalid,
+    Eof,
+}
+
+// Parses like `Parser::parse_root`, but reports errors and carries on so that
+// every problem in the input is found in one pass.
+pub(crate) struct Recovery<'a> {
+    parser: Parser<'a>,
+    diagnostics: Vec<Diagnostic>,
+    // A token that was read ahead and has not been handled yet.
+    pending: Option<Lexed>,
+}
+
+impl<'a> Recovery<'a> {
+    pub fn new(parser: Parser<'a>) -> Recovery<'a> {
+        Recovery {
+            parser,
+            diagnostics: Vec::new(),
+            pending: None,
+        }
+    }
+
+    fn report(&mut self, error: JsonError) {
+        // An error at the same position as the last one follows from it, such
+        // as the end of input leaving several containers unclosed.
+        let offset = error.span().map(|span| span.offset);
+        let last = self.diagnostics.last();
+        if last.is_some_and(|last| last.span().map(|span| span.offset) == offset) {
+            return;
+        }
+
+        self.diagnostics.push(Diagnostic { error });
+    }
+
+    fn next(&mut self) -> Lexed {
+        if let Some(lexed) = self.pending.take() {
+            return lexed;
+        }
+
+        match self.parser.lexer.next_token(self.parser.input) {
+            Ok(Some(token)) => Lexed::Token(token),
+            Ok(None) => Lexed::Eof,
+            Err(err) => {
+                self.report(err);
+                self.parser.lexer.skip_invalid(self.parser.input);
+                Lexed::Invalid
+            }
+        }
+    }
+
+    // Must be called while `token` is still the lexer's last token.
+    fn unexpected(&mut self, token: &Token, expected: &'static str) {
+        let err = Parser::unexpected(token, &self.parser, expected);
+        self.report(err);
+    }
+
+    fn end_of_input(&mut self, expected: &'static str) {
+        let span = self.parser.lexer.span();
+        self.report(JsonError::UnexpectedEof { expected, span });
+    }
+
+    // Whether a closing bracket that does not match the innermost container
+    // closes one further out, leaving the inner one unclosed. One that closes
+    // none of them was most likely meant to close the innermost.
+    fn closes_outer(stack: &[Frame<String, JsonValue>], kind: &TokenKind) -> bool {
+        stack.iter().any(|frame| {
+            matches!(
+                (frame, kind),
+                (Frame::Array(_), TokenKind::RightBracket)
+                    | (Frame::Object { .. }, TokenKind::RightBrace)
+            )
+        })
+    }
+
+    // Skips the rest of an array or object whose opening bracket was read.
+    fn skip_container(&mut self) {
+        let mut depth = 1;
+        loop {
+            match self.next() {
+                Lexed::Token(token) => match token.kind {
+                    TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+                    TokenKind::RightBracket | TokenKind::RightBrace => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return;
+                        }
+                    }
+                    _ => {}
+                },
+                Lexed::Invalid => {}
+                Lexed::Eof => return,
             }
+        }
+    }
 
-            last_seen_token = Some(token.kind);
+    // Skips the rest of a malformed object member up to the ',' that ends it,
+    // or up to a closing bracket, which is left to be read. Returns whether a
+    // ',' was skipped.
+    fn skip_member(&mut self) -> bool {
+        loop {
+            match self.next() {
+                Lexed::Token(token) => match token.kind {
+                    TokenKind::Comma => return true,
+                    TokenKind::LeftBracket | TokenKind::LeftBrace => self.skip_container(),
+                    TokenKind::RightBracket | TokenKind::RightBrace => {
+                        self.pending = Some(Lexed::Token(token));
+                        return false;
+                    }
+                    _ => {}
+                },
+                Lexed::Invalid => {}
+                Lexed::Eof => return false,
+            }
         }
+    }
 
-        Ok(JsonValue::Array(values))
+    // Opens an array or object, or skips it when `depth` containers are
+    // already open and it would exceed the depth limit.
+    fn check_depth(&mut self, depth: usize) -> bool {
+        match Parser::check_depth(&self.parser, depth) {
+            Ok(()) => true,
+            Err(err) => {
+                self.report(err);
+                self.skip_container();
+                false
+            }
+        }
     }
 
-    fn parse_object(parser: &mut Parser<'a>) -> Result<JsonValue, JsonError> {
-        let mut object: HashMap<String, JsonValue> = HashMap::new();
+    // Reads a member's key and its ':'. A key that fails to decode is kept as
+    // written and a missing ':' is reported and assumed.
+    fn member_key(&mut self, token: &Token, object: &Map) -> String {
+        let key = match Parser::decode_string(token, &self.parser) {
+            Ok(key) => key.into_owned(),
+            Err(err) => {
+                self.report(err);
+                Parser::resolve_string(token, self.parser.input).to_string()
+            }
+        };
+
+        if self.parser.options.duplicate_keys == DuplicateKeys::Error && object.contains_key(&key) {
+            self.report(JsonError::DuplicateKey {
+                key: key.clone(),
+                span: self.parser.lexer.last_token_span(),
+            });
+        }
+
+        match self.next() {
+            Lexed::Token(Token {
+                kind: TokenKind::Colon,
+                ..
+            }) => {}
+            Lexed::Token(token) => {
+                self.unexpected(&token, "':'");
+                self.pending = Some(Lexed::Token(token));
+            }
+            Lexed::Invalid => self.pending = Some(Lexed::Invalid),
+            Lexed::Eof => self.end_of_input("':'"),
+        }
+
+        key
+    }
+
+    // Reads the key of the next member, skipping members that do not start
+    // with one. Returns `None` when the object ends instead.
+    fn key(
+        &mut self,
+        stack: &[Frame<String, JsonValue>],
+        object: &Map,
+        after_comma: bool,
+    ) -> Option<String> {
+        let expected = if after_comma {
+            "a string key"
+        } else {
+            "a string key or '}'"
+        };
+        let mut may_close = !after_comma;
+
         loop {
-            if parser.lexer.pos as usize > parser.input.len() {
-                break;
+            let token = match self.next() {
+                Lexed::Token(token) => token,
+                Lexed::Invalid => {
+                    may_close = !self.skip_member();
+                    continue;
+                }
+                Lexed::Eof => {
+                    self.end_of_input(expected);
+                    return None;
+                }
+            };
+
+            match token.kind {
+                TokenKind::String => return Some(self.member_key(&token, object)),
+                TokenKind::RightBrace => {
+                    if !may_close {
+                        self.unexpected(&token, expected);
+                    }
+                    return None;
+                }
+                TokenKind::RightBracket => {
+                    self.unexpected(&token, expected);
+                    if Self::closes_outer(stack, &token.kind) {
+                        self.pending = Some(Lexed::Token(token));
+                    }
+                    return None;
+                }
+                TokenKind::Comma => self.unexpected(&token, expected),
+                _ => {
+                    self.unexpected(&token, expected);
+                    may_close = !self.skip_member();
+                }
             }
+        }
+    }
+
+    // Reads what follows a value in an array or object. Returns `true` for a
+    // ',' and `false` when the container ends, which a mismatched closing
+    // bracket and the end of input also do. A value where a ',' was
+    // expected is reported and read as if the ',' was there.
+    fn separator(&mut self, stack: &[Frame<String, JsonValue>], array: bool) -> bool {
+        let expected = if array { "',' or ']'" } else { "',' or '}'" };
 
-            let token = parser
-                .lexer
-                .next_token(parser.input)
-                .ok_or(JsonError::UnexpectedEof)?;
+        loop {
+            let token = match self.next() {
+                Lexed::Token(token) => token,
+                Lexed::Invalid => continue,
+                Lexed::Eof => {
+                    self.end_of_input(expected);
+                    return false;
+                }
+            };
 
             match token.kind {
-                TokenKind::String => {
-                    let key = Self::resolve_string(&token, parser.input).to_string();
-                    if let Some(_object_key) = object.get(&key) {
-                        return Err(JsonError::DuplicateKey(key.to_string()));
-                    }
-
-                    Self::expect_colon(parser)?;
-                    let next_token = parser
-                        .lexer
-                        .next_token(parser.input)
-                        .ok_or(JsonError::UnexpectedEof)?;
-
-                    let value = match next_token.kind {
-                        TokenKind::Null => JsonValue::Null,
-                        TokenKind::String => {
-                            let start = next_token.start as usize;
-                            let end = next_token.end as usize;
-                            JsonValue::String(
-                                std::str::from_utf8(&parser.input[start as usize..end as usize])
-                                    .unwrap()
-                                    .to_string(),
-                            )
-                        }
-                        TokenKind::Number => {
-                            let start = next_token.start as usize;
-                            let end = next_token.end as usize;
-                            JsonValue::Number(Self::resolve_number(&parser.input[start..end])?)
+                TokenKind::Comma => return true,
+                TokenKind::RightBracket | TokenKind::RightBrace => {
+                    if array == matches!(token.kind, TokenKind::RightBracket) {
+                        return false;
+                    }
+
+                    self.unexpected(&token, expected);
+                    if Self::closes_outer(stack, &token.kind) {
+                        self.pending = Some(Lexed::Token(token));
+                    }
+                    return false;
+                }
+                TokenKind::Colon => self.unexpected(&token, expected),
+                _ => {
+                    self.unexpected(&token, expected);
+                    self.pending = Some(Lexed::Token(token));
+                    return true;
+                }
+            }
+        }
+    }
+
+    // Follows `Parser::parse_root`. `value` is `None` where an array ends
+    // right after a ',', so nothing is added for it.
+    fn parse_root(&mut self) -> Option<JsonValue> {
+        let mut stack: Vec<Frame<String, JsonValue>> = Vec::new();
+        // Whether an array has just been opened, so that ']' may close it.
+        let mut may_close = false;
+
+        loop {
+            let expected = if may_close {
+                "a value or ']'"
+            } else {
+                "a value"
+            };
+            let mut value = match self.next() {
+                Lexed::Token(token) => match token.kind {
+                    TokenKind::LeftBracket => {
+                        if !self.check_depth(stack.len()) {
+                            Some(JsonValue::Null)
+                        } else {
+                            match self.next() {
+                                Lexed::Token(Token {
+                                    kind: TokenKind::RightBracket,
+                                    ..
+                                }) => Some(JsonValue::Array(Vec::new())),
+                                lexed => {
+                                    self.pending = Some(lexed);
+                                    stack.push(Frame::Array(Vec::new()));
+                                    may_close = true;
+                                    continue;
+                                }
+                            }
                         }
-                        TokenKind::True | TokenKind::False => {
-                            JsonValue::Boolean(matches!(next_token.kind, TokenKind::True))
+                    }
+                    TokenKind::LeftBrace => {
+                        if !self.check_depth(stack.len()) {
+                            Some(JsonValue::Null)
+                        } else {
+                            let object = Map::new();
+                            match self.key(&stack, &object, false) {
+                                Some(key) => {
+                                    stack.push(Frame::Object {
+                                        object,
+                                        key,
+                                        collected: HashSet::new(),
+                                    });
+                                    may_close = false;
+                                    continue;
+                                }
+                                None => Some(JsonValue::Object(object)),
+                            }
                         }
-                        TokenKind::LeftBrace => Self::parse_object(parser)?,
-                        TokenKind::LeftBracket => Self::parse_array(parser)?,
-                        TokenKind::Comma => continue,
-                        _ => {
-                            return Err(JsonError::UnexpectedToken {
-                                line: parser.lexer.last_token_line as usize,
-                                col: parser.lexer.last_token_col as usize,
-                            })
+                    }
+                    // Only reached after stray colons, which are skipped
+                    // below without ending the array that was just opened.
+                    TokenKind::RightBracket if may_close => {
+                        self.pending = Some(Lexed::Token(token));
+                        None
+                    }
+                    TokenKind::Comma
+                    | TokenKind::Colon
+                    | TokenKind::RightBracket
+                    | TokenKind::RightBrace => {
+                        self.unexpected(&token, expected);
+
+                        // A missing value stands in as null, except before
+                        // the end of an array, where it is most likely a
+                        // trailing comma.
+                        let placeholder = match (stack.last(), &token.kind) {
+                            (None, _) | (_, TokenKind::Colon) => continue,
+                            (
+                                Some(Frame::Array(_)),
+                                TokenKind::RightBracket | TokenKind::RightBrace,
+                            ) => None,
+                            _ => Some(JsonValue::Null),
+                        };
+                        self.pending = Some(Lexed::Token(token));
+                        placeholder
+                    }
+                    _ => match Parser::parse_scalar(&token, &mut self.parser, expected) {
+                        Ok(value) => Some(value),
+                        Err(err) => {
+                            self.report(err);
+                            Some(JsonValue::Null)
                         }
-                    };
-                    object.insert(key.to_string(), value);
+                    },
+                },
+                Lexed::Invalid => Some(JsonValue::Null),
+                Lexed::Eof => {
+                    self.end_of_input(expected);
+                    match stack.last() {
+                        None => return None,
+                        Some(Frame::Array(_)) => None,
+                        Some(Frame::Object { .. }) => Some(JsonValue::Null),
+                    }
                 }
-                TokenKind::Comma => continue,
-                TokenKind::RightBrace => break,
-                _ => return Err(JsonError::UnexpectedEof),
+            };
+
+            loop {
+                let Some(frame) = stack.pop() else {
+                    return value;
+                };
+
+                value = match frame {
+                    Frame::Array(mut values) => {
+                        values.extend(value);
+
+                        if self.separator(&stack, true) {
+                            stack.push(Frame::Array(values));
+                            break;
+                        }
+                        Some(JsonValue::Array(values))
+                    }
+                    Frame::Object {
+                        mut object,
+                        key,
+                        mut collected,
+                    } => {
+                        if let Some(value) = value {
+                            let parser = &self.parser;
+                            Parser::insert_member(parser, &mut object, &mut collected, key, value);
+                        }
+
+                        let key = if self.separator(&stack, false) {
+                            self.key(&stack, &object, true)
+                        } else {
+                            None
+                        };
+                        if let Some(key) = key {
+                            stack.push(Frame::Object {
+                                object,
+                                key,
+                                collected,
+                            });
+                            break;
+                        }
+                        Some(JsonValue::Object(object))
+                    }
+                };
             }
+
+            may_close = false;
         }
-        Ok(JsonValue::Object(object))
-    }
-
-    pub fn parse(mut self) -> Result<JsonValue, JsonError> {
-        let token = self
-            .lexer
-            .next_token(self.input)
-            .ok_or(JsonError::UnexpectedEof)?;
-        let value = match token.kind {
-            TokenKind::LeftBrace => Self::parse_object(&mut self)?,
-            TokenKind::LeftBracket => Self::parse_array(&mut self)?,
-            TokenKind::Number => Self::parse_number(&token, &mut self)?,
-            TokenKind::True | TokenKind::False => Self::parse_boolean(&token, &mut self)?,
-            TokenKind::String => Self::parse_string(&token, &mut self)?,
-            TokenKind::Null => JsonValue::Null,
-            _ => {
-                return Err(JsonError::UnexpectedToken {
-                    line: self.lexer.last_token_line as usize,
-                    col: self.lexer.last_token_col as usize,
-                })
+    }
+
+    pub fn parse(mut self) -> (Option<JsonValue>, Vec<Diagnostic>) {
+        let value = self.parse_root();
+
+        if value.is_some() {
+            self.parser.lexer.skip_whitespace(self.parser.input);
+            if self.parser.lexer.pos < self.parser.input.len() {
+                let span = self.parser.lexer.span();
+                self.report(JsonError::TrailingCharacters { span });
             }
-        };
-        Ok(value)
+        }
+
+        (value, self.diagnostics)
     }
 }
@@ -0,0 +1,741 @@
+use crate::{de::NUMBER_TOKEN, JsonError, JsonValue, Map, Number};
+use serde::ser::{
+    self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct,
+    SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+use std::fmt::{self, Write};
+
+/// Serialises `value` as compact JSON text.
+///
+/// The text is written directly, without building a [`JsonValue`] first.
+/// Enums are externally tagged, non-finite floats are written as `null`, and
+/// map keys must be strings, chars or integers, which are written as strings.
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// let text = rsjson::to_string(&vec![(1, "a"), (2, "b")]).unwrap();
+/// assert_eq!(text, r#"[[1,"a"],[2,"b"]]"#);
+/// # }
+/// ```
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, JsonError> {
+    let mut serializer = Serializer {
+        output: String::new(),
+    };
+    value.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+impl ser::Error for JsonError {
+    fn custom<T: fmt::Display>(message: T) -> JsonError {
+        <JsonError as serde::de::Error>::custom(message)
+    }
+}
+
+struct Serializer {
+    output: String,
+}
+
+impl Serializer {
+    // Writing to a `String` cannot fail.
+    fn write(&mut self, value: impl fmt::Display) {
+        let _ = write!(self.output, "{value}");
+    }
+
+    fn write_str(&mut self, str: &str) {
+        let _ = JsonValue::write_escaped(&mut self.output, str);
+    }
+
+    // Opens a compound value, where `close` is written when it ends.
+    fn open(&mut self, open: &str, close: &'static str) -> Compound<'_> {
+        self.output.push_str(open);
+        Compound {
+            ser: self,
+            first: true,
+            close,
+        }
+    }
+
+    // Opens the object that tags a variant, leaving its value to be written.
+    fn open_variant(&mut self, variant: &str) {
+        self.output.push('{');
+        self.write_str(variant);
+        self.output.push(':');
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = JsonError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, b: bool) -> Result<(), JsonError> {
+        self.write(b);
+        Ok(())
+    }
+
+    fn serialize_i8(self, n: i8) -> Result<(), JsonError> {
+        self.serialize_i64(n.into())
+    }
+
+    fn serialize_i16(self, n: i16) -> Result<(), JsonError> {
+        self.serialize_i64(n.into())
+    }
+
+    fn serialize_i32(self, n: i32) -> Result<(), JsonError> {
+        self.serialize_i64(n.into())
+    }
+
+    fn serialize_i64(self, n: i64) -> Result<(), JsonError> {
+        self.write(n);
+        Ok(())
+    }
+
+    fn serialize_i128(self, n: i128) -> Result<(), JsonError> {
+        self.write(n);
+        Ok(())
+    }
+
+    fn serialize_u8(self, n: u8) -> Result<(), JsonError> {
+        self.serialize_u64(n.into())
+    }
+
+    fn serialize_u16(self, n: u16) -> Result<(), JsonError> {
+        self.serialize_u64(n.into())
+    }
+
+    fn serialize_u32(self, n: u32) -> Result<(), JsonError> {
+        self.serialize_u64(n.into())
+    }
+
+    fn serialize_u64(self, n: u64) -> Result<(), JsonError> {
+        self.write(n);
+        Ok(())
+    }
+
+    fn serialize_u128(self, n: u128) -> Result<(), JsonError> {
+        self.write(n);
+        Ok(())
+    }
+
+    fn serialize_f32(self, n: f32) -> Result<(), JsonError> {
+        self.serialize_f64(n.into())
+    }
+
+    fn serialize_f64(self, n: f64) -> Result<(), JsonError> {
+        self.write(Number::from(n));
+        Ok(())
+    }
+
+    fn serialize_char(self, ch: char) -> Result<(), JsonError> {
+        self.serialize_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, str: &str) -> Result<(), JsonError> {
+        self.write_str(str);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, bytes: &[u8]) -> Result<(), JsonError> {
+        self.collect_seq(bytes)
+    }
+
+    fn serialize_none(self) -> Result<(), JsonError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), JsonError> {
+        self.output.push_str("null");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), JsonError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        if name == NUMBER_TOKEN {
+            // The raw text of a number, written as a string and unquoted. It
+            // is all digits, signs, points and exponents, so nothing is escaped.
+            let mut text = Serializer {
+                output: String::new(),
+            };
+            value.serialize(&mut text)?;
+            self.output.push_str(text.output.trim_matches('"'));
+            return Ok(());
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.open_variant(variant);
+        value.serialize(&mut *self)?;
+        self.output.push('}');
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, JsonError> {
+        Ok(self.open("[", "]"))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, JsonError> {
+        self.open_variant(variant);
+        Ok(self.open("[", "]}"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, JsonError> {
+        Ok(self.open("{", "}"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, JsonError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, JsonError> {
+        self.open_variant(variant);
+        Ok(self.open("{", "}}"))
+    }
+}
+
+// An array or object being written.
+struct Compound<'a> {
+    ser: &'a mut Serializer,
+    first: bool,
+    close: &'static str,
+}
+
+impl Compound<'_> {
+    fn separate(&mut self) {
+        if !self.first {
+            self.ser.output.push(',');
+        }
+        self.first = false;
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.separate();
+        value.serialize(&mut *self.ser)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), JsonError> {
+        self.separate();
+        self.ser.write_str(key);
+        self.ser.output.push(':');
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        self.ser.output.push_str(self.close);
+        Ok(())
+    }
+}
+
+impl SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+impl SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+impl SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+impl SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+impl SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JsonError> {
+        self.separate();
+        key.serialize(KeySerializer { ser: self.ser })
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.ser.output.push(':');
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+impl SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+impl SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        Compound::end(self)
+    }
+}
+
+// Writes a map key, which must become a string.
+struct KeySerializer<'a> {
+    ser: &'a mut Serializer,
+}
+
+impl KeySerializer<'_> {
+    fn quoted(self, n: impl fmt::Display) -> Result<(), JsonError> {
+        self.ser.output.push('"');
+        self.ser.write(n);
+        self.ser.output.push('"');
+        Ok(())
+    }
+}
+
+fn key_must_be_a_string() -> JsonError {
+    ser::Error::custom("object keys must be strings")
+}
+
+impl ser::Serializer for KeySerializer<'_> {
+    type Ok = ();
+    type Error = JsonError;
+    type SerializeSeq = Impossible<(), JsonError>;
+    type SerializeTuple = Impossible<(), JsonError>;
+    type SerializeTupleStruct = Impossible<(), JsonError>;
+    type SerializeTupleVariant = Impossible<(), JsonError>;
+    type SerializeMap = Impossible<(), JsonError>;
+    type SerializeStruct = Impossible<(), JsonError>;
+    type SerializeStructVariant = Impossible<(), JsonError>;
+
+    fn serialize_str(self, str: &str) -> Result<(), JsonError> {
+        self.ser.write_str(str);
+        Ok(())
+    }
+
+    fn serialize_char(self, ch: char) -> Result<(), JsonError> {
+        self.serialize_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_i8(self, n: i8) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_i16(self, n: i16) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_i32(self, n: i32) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_i64(self, n: i64) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_i128(self, n: i128) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_u8(self, n: u8) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_u16(self, n: u16) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_u32(self, n: u32) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_u64(self, n: u64) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_u128(self, n: u128) -> Result<(), JsonError> {
+        self.quoted(n)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), JsonError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _b: bool) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f32(self, _n: f32) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _n: f64) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_bytes(self, _bytes: &[u8]) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, JsonError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, JsonError> {
+        Err(key_must_be_a_string())
+    }
+}
+
+impl Serialize for JsonValue {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+            JsonValue::Number(n) => n.serialize(serializer),
+            JsonValue::String(str) => serializer.serialize_str(str),
+            JsonValue::Array(values) => serializer.collect_seq(values),
+            JsonValue::Object(map) => map.serialize(serializer),
+        }
+    }
+}
+
+/// Integers are serialised exactly, as 128-bit integers when their raw text is
+/// too wide for 64 bits. Other raw text that an `f64` would not write back the
+/// same, such as `1.50`, is given to serializers as a newtype struct holding
+/// the text, which [`to_string`] writes as the number.
+impl Serialize for Number {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(n) = self.as_u64() {
+            return serializer.serialize_u64(n);
+        }
+        if let Some(n) = self.as_i64() {
+            return serializer.serialize_i64(n);
+        }
+
+        let Some(raw) = self.raw() else {
+            return serializer.serialize_f64(self.as_f64());
+        };
+        if let Some(n) = self.wide_integer::<u128>() {
+            serializer.serialize_u128(n)
+        } else if let Some(n) = self.wide_integer::<i128>() {
+            serializer.serialize_i128(n)
+        } else if Number::from(self.as_f64()).to_string() == raw {
+            serializer.serialize_f64(self.as_f64())
+        } else {
+            serializer.serialize_newtype_struct(NUMBER_TOKEN, raw)
+        }
+    }
+}
+
+impl<V: Serialize> Serialize for Map<String, V> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        from_str, parse, parse_with_options, to_string, ErrorKind, JsonValue, Number, ParseOptions,
+    };
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Job {
+        name: String,
+        retries: Option<u8>,
+        weight: f64,
+        shape: Shape,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Line(i32, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[test]
+    fn serialises_compact_text() {
+        let job = Job {
+            name: "say \"hi\"\n".to_string(),
+            retries: None,
+            weight: 1.0,
+            shape: Shape::Rect { w: 2, h: 3 },
+        };
+        let text = to_string(&job).unwrap();
+        assert_eq!(
+            text,
+            r#"{"name":"say \"hi\"\n","retries":null,"weight":1.0,"shape":{"Rect":{"w":2,"h":3}}}"#
+        );
+        assert_eq!(from_str::<Job>(&text).unwrap(), job);
+
+        let shapes = [Shape::Point, Shape::Circle(0.5), Shape::Line(-1, 1)];
+        let text = to_string(&shapes).unwrap();
+        assert_eq!(text, r#"["Point",{"Circle":0.5},{"Line":[-1,1]}]"#);
+        assert_eq!(from_str::<Vec<Shape>>(&text).unwrap(), shapes);
+
+        assert_eq!(to_string(&f64::NAN).unwrap(), "null");
+        assert_eq!(to_string(&'\u{1}').unwrap(), r#""\u0001""#);
+    }
+
+    #[test]
+    fn map_keys_must_be_strings() {
+        let map = BTreeMap::from([(1, "a"), (-2, "b")]);
+        assert_eq!(to_string(&map).unwrap(), r#"{"-2":"b","1":"a"}"#);
+
+        let err = to_string(&BTreeMap::from([((1, 2), 3)])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Custom);
+        assert_eq!(err.to_string(), "object keys must be strings");
+        assert_eq!(err.span(), None);
+        assert_eq!(err.to_json()["span"], JsonValue::Null);
+        assert_eq!(
+            err.render("").to_string(),
+            "error[E0013]: object keys must be strings"
+        );
+    }
+
+    #[test]
+    fn round_trips_wide_integers() {
+        let values = (u128::MAX, i128::MIN, 1u128, -1i128);
+        let text = to_string(&values).unwrap();
+        assert_eq!(
+            text,
+            "[340282366920938463463374607431768211455,\
+             -170141183460469231731687303715884105728,1,-1]"
+        );
+        assert_eq!(from_str::<(u128, i128, u128, i128)>(&text).unwrap(), values);
+
+        let map = BTreeMap::from([(u128::MAX, true), (0, false)]);
+        let text = to_string(&map).unwrap();
+        assert_eq!(from_str::<BTreeMap<u128, bool>>(&text).unwrap(), map);
+
+        assert!(from_str::<f64>("340282366920938463463374607431768211455").is_ok());
+
+        for value in [JsonValue::from(u128::MAX), JsonValue::from(i128::MIN)] {
+            let text = to_string(&value).unwrap();
+            assert_eq!(text, value.to_string());
+            assert_eq!(from_str::<JsonValue>(&text).unwrap(), value);
+            assert_eq!(
+                from_str::<Number>(&text).unwrap(),
+                value.as_number().unwrap().clone()
+            );
+        }
+        let value = json!({"a": [u128::MAX, 1, -2]});
+        assert_eq!(
+            from_str::<JsonValue>(&to_string(&value).unwrap()).unwrap(),
+            value
+        );
+        assert!(from_str::<u128>("-1").is_err());
+        assert!(from_str::<i128>("1.5").is_err());
+    }
+
+    #[test]
+    fn serialises_raw_numbers_exactly() {
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..ParseOptions::default()
+        };
+        let text = "[123456789012345678901234567890,1.50,-0,2.5,1E3,12345678901234567890123456789012345678901]";
+        let value = parse_with_options(text, &options).unwrap();
+        assert_eq!(to_string(&value).unwrap(), text);
+    }
+
+    #[test]
+    fn serialises_values() {
+        let value = json!({"b": [1, -2, 2.5, "s", true, null], "a": {}});
+        let text = to_string(&value).unwrap();
+        assert_eq!(text, r#"{"b":[1,-2,2.5,"s",true,null],"a":{}}"#);
+        assert_eq!(parse(&text).unwrap(), value);
+        assert_eq!(from_str::<JsonValue>(&text).unwrap(), value);
+    }
+}
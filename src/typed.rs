@@ -0,0 +1,614 @@
+use crate::{JsonKind, JsonValue, Map, Number};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::{BuildHasher, Hash},
+};
+
+/// Converts a Rust value into a [`JsonValue`].
+///
+/// With the `derive` feature, `#[derive(ToJson)]` implements this for structs
+/// with named fields.
+pub trait ToJson {
+    fn to_json(&self) -> JsonValue;
+}
+
+/// Builds a Rust value from a [`JsonValue`].
+///
+/// With the `derive` feature, `#[derive(FromJson)]` implements this for
+/// structs with named fields.
+pub trait FromJson: Sized {
+    fn from_json(value: &JsonValue) -> Result<Self, FromJsonError>;
+
+    /// The value for a struct field that is missing from the object, or
+    /// `None` if the field is required. Only `Option` fields may be missing.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Why a [`JsonValue`] could not be converted by [`FromJson`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FromJsonErrorKind {
+    /// The value is of the wrong kind, such as a string where an array was
+    /// expected.
+    InvalidType {
+        expected: JsonKind,
+        found: JsonKind,
+    },
+    /// A number that does not fit the target type, such as `300` for a `u8`
+    /// or `1.5` for an integer.
+    InvalidNumber {
+        expected: &'static str,
+        found: Number,
+    },
+    /// An array of the wrong length for a tuple.
+    InvalidLength {
+        expected: usize,
+        found: usize,
+    },
+    /// A required field is missing. Its name is the last part of the path.
+    MissingField,
+    Custom(String),
+}
+
+/// A [`FromJson`] conversion error, with the JSON path of the value that
+/// failed to convert.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromJsonError {
+    kind: FromJsonErrorKind,
+    // Innermost first, as segments are added while the error is returned
+    // through the enclosing values.
+    path: Vec<PathSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl FromJsonError {
+    pub fn new(kind: FromJsonErrorKind) -> FromJsonError {
+        FromJsonError {
+            kind,
+            path: Vec::new(),
+        }
+    }
+
+    pub fn custom(message: impl fmt::Display) -> FromJsonError {
+        FromJsonError::new(FromJsonErrorKind::Custom(message.to_string()))
+    }
+
+    /// An error for `found` not being of the `expected` kind.
+    pub fn invalid_type(expected: JsonKind, found: &JsonValue) -> FromJsonError {
+        FromJsonError::new(FromJsonErrorKind::InvalidType {
+            expected,
+            found: found.kind(),
+        })
+    }
+
+    /// An error for the required field `name` being missing.
+    pub fn missing_field(name: &str) -> FromJsonError {
+        FromJsonError::new(FromJsonErrorKind::MissingField).at_key(name)
+    }
+
+    /// Places the error under the object member `key`, for conversions of
+    /// containers to call when converting a member fails.
+    pub fn at_key(mut self, key: &str) -> FromJsonError {
+        self.path.push(PathSegment::Key(key.to_string()));
+        self
+    }
+
+    /// Places the error under the array element `index`.
+    pub fn at_index(mut self, index: usize) -> FromJsonError {
+        self.path.push(PathSegment::Index(index));
+        self
+    }
+
+    pub fn kind(&self) -> &FromJsonErrorKind {
+        &self.kind
+    }
+
+    /// The JSON Pointer of the value that failed to convert, such as
+    /// `/users/3/name`, which [`JsonValue::pointer`] resolves. It is empty
+    /// when the error is about the value itself.
+    pub fn pointer(&self) -> String {
+        let mut pointer = String::new();
+        for segment in self.path.iter().rev() {
+            pointer.push('/');
+            match segment {
+                PathSegment::Key(key) => {
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"))
+                }
+                PathSegment::Index(index) => pointer.push_str(&index.to_string()),
+            }
+        }
+        pointer
+    }
+}
+
+impl std::error::Error for FromJsonError {}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FromJsonErrorKind::InvalidType { expected, found } => {
+                write!(f, "expected {expected}, got {found}")?
+            }
+            FromJsonErrorKind::InvalidNumber { expected, found } => {
+                write!(f, "expected {expected}, got {found}")?
+            }
+            FromJsonErrorKind::InvalidLength { expected, found } => write!(
+                f,
+                "expected an array of length {expected}, got length {found}"
+            )?,
+            FromJsonErrorKind::MissingField => f.write_str("missing field")?,
+            FromJsonErrorKind::Custom(message) => f.write_str(message)?,
+        }
+
+        if !self.path.is_empty() {
+            write!(f, " at {}", self.pointer())?;
+        }
+        Ok(())
+    }
+}
+
+impl ToJson for JsonValue {
+    fn to_json(&self) -> JsonValue {
+        self.clone()
+    }
+}
+
+impl FromJson for JsonValue {
+    fn from_json(value: &JsonValue) -> Result<JsonValue, FromJsonError> {
+        Ok(value.clone())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Boolean(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JsonValue) -> Result<bool, FromJsonError> {
+        value
+            .as_bool()
+            .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Boolean, value))
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::String(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::String(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JsonValue) -> Result<String, FromJsonError> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| FromJsonError::invalid_type(JsonKind::String, value))
+    }
+}
+
+impl ToJson for Number {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Number(self.clone())
+    }
+}
+
+impl FromJson for Number {
+    fn from_json(value: &JsonValue) -> Result<Number, FromJsonError> {
+        value
+            .as_number()
+            .cloned()
+            .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Number, value))
+    }
+}
+
+// Integers convert from numbers that are integers in the target's range,
+// checked against the `i64` or `u64` the number is stored as.
+macro_rules! integer {
+    ($as:ident => $($ty:ty),*) => {
+        $(
+            impl ToJson for $ty {
+                fn to_json(&self) -> JsonValue {
+                    JsonValue::Number(Number::from(*self))
+                }
+            }
+
+            impl FromJson for $ty {
+                fn from_json(value: &JsonValue) -> Result<$ty, FromJsonError> {
+                    let number = value
+                        .as_number()
+                        .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Number, value))?;
+
+                    number.$as().and_then(|n| <$ty>::try_from(n).ok()).ok_or_else(|| {
+                        FromJsonError::new(FromJsonErrorKind::InvalidNumber {
+                            expected: stringify!($ty),
+                            found: number.clone(),
+                        })
+                    })
+                }
+            }
+        )*
+    };
+}
+
+integer!(as_u64 => u8, u16, u32, u64, usize);
+integer!(as_i64 => i8, i16, i32, i64, isize);
+
+// 128-bit integers also convert from integers too wide for 64 bits, which are
+// exact only when their raw text was kept, as it is by `From<u128>`.
+macro_rules! wide_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl ToJson for $ty {
+                fn to_json(&self) -> JsonValue {
+                    JsonValue::Number(Number::from(*self))
+                }
+            }
+
+            impl FromJson for $ty {
+                fn from_json(value: &JsonValue) -> Result<$ty, FromJsonError> {
+                    let number = value
+                        .as_number()
+                        .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Number, value))?;
+
+                    let n = match (number.as_u64(), number.as_i64()) {
+                        (Some(n), _) => <$ty>::try_from(n).ok(),
+                        (None, Some(n)) => <$ty>::try_from(n).ok(),
+                        (None, None) => number.wide_integer(),
+                    };
+                    n.ok_or_else(|| {
+                        FromJsonError::new(FromJsonErrorKind::InvalidNumber {
+                            expected: stringify!($ty),
+                            found: number.clone(),
+                        })
+                    })
+                }
+            }
+        )*
+    };
+}
+
+wide_integer!(u128, i128);
+
+macro_rules! float {
+    ($($ty:ty),*) => {
+        $(
+            impl ToJson for $ty {
+                fn to_json(&self) -> JsonValue {
+                    JsonValue::Number(Number::from(*self))
+                }
+            }
+
+            /// Converts from any number, rounding to the nearest value.
+            impl FromJson for $ty {
+                fn from_json(value: &JsonValue) -> Result<$ty, FromJsonError> {
+                    value
+                        .as_f64()
+                        .map(|n| n as $ty)
+                        .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Number, value))
+                }
+            }
+        )*
+    };
+}
+
+float!(f32, f64);
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> JsonValue {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> JsonValue {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(value: &JsonValue) -> Result<Box<T>, FromJsonError> {
+        T::from_json(value).map(Box::new)
+    }
+}
+
+/// `None` converts to `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> JsonValue {
+        self.as_ref().map_or(JsonValue::Null, ToJson::to_json)
+    }
+}
+
+/// `null` and missing fields convert to `None`.
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &JsonValue) -> Result<Option<T>, FromJsonError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Option<T>> {
+        Some(None)
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> JsonValue {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JsonValue) -> Result<Vec<T>, FromJsonError> {
+        let values = value
+            .as_array()
+            .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Array, value))?;
+
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| T::from_json(value).map_err(|err| err.at_index(i)))
+            .collect()
+    }
+}
+
+// Converts the members of an object, for the map types.
+fn members<K, V, M>(value: &JsonValue) -> Result<M, FromJsonError>
+where
+    K: From<String>,
+    V: FromJson,
+    M: FromIterator<(K, V)>,
+{
+    let object = value
+        .as_object()
+        .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Object, value))?;
+
+    object
+        .iter()
+        .map(|(key, value)| match V::from_json(value) {
+            Ok(value) => Ok((K::from(key.clone()), value)),
+            Err(err) => Err(err.at_key(key)),
+        })
+        .collect()
+}
+
+impl<T: ToJson> ToJson for Map<String, T> {
+    fn to_json(&self) -> JsonValue {
+        let members = self
+            .iter()
+            .map(|(key, value)| (key.clone(), value.to_json()));
+        JsonValue::Object(members.collect())
+    }
+}
+
+impl<T: FromJson> FromJson for Map<String, T> {
+    fn from_json(value: &JsonValue) -> Result<Map<String, T>, FromJsonError> {
+        members(value)
+    }
+}
+
+/// The members keep the map's iteration order, which for a `HashMap` is
+/// arbitrary.
+impl<K: AsRef<str>, V: ToJson, S> ToJson for HashMap<K, V, S> {
+    fn to_json(&self) -> JsonValue {
+        let members = self
+            .iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_json()));
+        JsonValue::Object(members.collect())
+    }
+}
+
+impl<K, V, S> FromJson for HashMap<K, V, S>
+where
+    K: From<String> + Hash + Eq,
+    V: FromJson,
+    S: BuildHasher + Default,
+{
+    fn from_json(value: &JsonValue) -> Result<HashMap<K, V, S>, FromJsonError> {
+        members(value)
+    }
+}
+
+impl<K: AsRef<str>, V: ToJson> ToJson for BTreeMap<K, V> {
+    fn to_json(&self) -> JsonValue {
+        let members = self
+            .iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_json()));
+        JsonValue::Object(members.collect())
+    }
+}
+
+impl<K: From<String> + Ord, V: FromJson> FromJson for BTreeMap<K, V> {
+    fn from_json(value: &JsonValue) -> Result<BTreeMap<K, V>, FromJsonError> {
+        members(value)
+    }
+}
+
+// Tuples convert to and from arrays of the same length.
+macro_rules! tuple {
+    ($len:literal => $($name:ident $index:tt),+) => {
+        impl<$($name: ToJson),+> ToJson for ($($name,)+) {
+            fn to_json(&self) -> JsonValue {
+                JsonValue::Array(vec![$(self.$index.to_json()),+])
+            }
+        }
+
+        impl<$($name: FromJson),+> FromJson for ($($name,)+) {
+            fn from_json(value: &JsonValue) -> Result<($($name,)+), FromJsonError> {
+                let values = value
+                    .as_array()
+                    .ok_or_else(|| FromJsonError::invalid_type(JsonKind::Array, value))?;
+                if values.len() != $len {
+                    return Err(FromJsonError::new(FromJsonErrorKind::InvalidLength {
+                        expected: $len,
+                        found: values.len(),
+                    }));
+                }
+
+                Ok(($(
+                    $name::from_json(&values[$index]).map_err(|err| err.at_index($index))?,
+                )+))
+            }
+        }
+    };
+}
+
+tuple!(1 => A 0);
+tuple!(2 => A 0, B 1);
+tuple!(3 => A 0, B 1, C 2);
+tuple!(4 => A 0, B 1, C 2, D 3);
+tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn from_str<T: FromJson>(input: &str) -> Result<T, FromJsonError> {
+        T::from_json(&parse(input).unwrap())
+    }
+
+    #[test]
+    fn scalars_round_trip() {
+        assert_eq!(from_str::<bool>("true"), Ok(true));
+        assert_eq!(from_str::<String>(r#""text""#).as_deref(), Ok("text"));
+        assert_eq!(from_str::<f64>("1.5"), Ok(1.5));
+        assert_eq!(from_str::<f32>("2"), Ok(2.0));
+        assert_eq!(from_str::<u64>("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(from_str::<i8>("-128"), Ok(i8::MIN));
+        assert_eq!(from_str::<Option<u8>>("null"), Ok(None));
+        assert_eq!(from_str::<Option<u8>>("7"), Ok(Some(7)));
+
+        assert_eq!(42u8.to_json(), parse("42").unwrap());
+        assert_eq!((-1i64).to_json(), parse("-1").unwrap());
+        assert_eq!("s".to_json(), parse(r#""s""#).unwrap());
+        assert_eq!(None::<bool>.to_json(), JsonValue::Null);
+    }
+
+    #[test]
+    fn integers_are_range_checked() {
+        let err = from_str::<u8>("300").unwrap_err();
+        assert_eq!(err.to_string(), "expected u8, got 300");
+        assert_eq!(
+            from_str::<u32>("-1").unwrap_err().to_string(),
+            "expected u32, got -1"
+        );
+        assert_eq!(
+            from_str::<i64>("1.5").unwrap_err().to_string(),
+            "expected i64, got 1.5"
+        );
+        assert_eq!(
+            from_str::<i16>(r#""1""#).unwrap_err().to_string(),
+            "expected number, got string"
+        );
+    }
+
+    #[test]
+    fn wide_integers_round_trip() {
+        for n in [u128::MAX, u64::MAX as u128 + 1, 7] {
+            assert_eq!(u128::from_json(&n.to_json()), Ok(n));
+        }
+        for n in [i128::MIN, i128::MAX, i64::MIN as i128 - 1, -7] {
+            assert_eq!(i128::from_json(&n.to_json()), Ok(n));
+        }
+        assert_eq!(from_str::<i128>("-1"), Ok(-1));
+
+        let options = crate::ParseOptions {
+            raw_numbers: true,
+            ..crate::ParseOptions::default()
+        };
+        let parse_raw = |text| crate::parse_with_options(text, &options).unwrap();
+        assert_eq!(
+            u128::from_json(&parse_raw("340282366920938463463374607431768211455")),
+            Ok(u128::MAX)
+        );
+
+        let out_of_range = [
+            ("-1", "expected u128, got -1"),
+            (
+                "340282366920938463463374607431768211456",
+                "expected u128, got 340282366920938463463374607431768211456",
+            ),
+            ("1.5", "expected u128, got 1.5"),
+            ("-0", "expected u128, got -0"),
+        ];
+        for (text, message) in out_of_range {
+            let err = u128::from_json(&parse_raw(text)).unwrap_err();
+            assert_eq!(err.to_string(), message);
+        }
+        assert!(i128::from_json(&(u128::MAX).to_json()).is_err());
+        // Without the raw text, a wide integer is only known as an `f64`.
+        assert!(from_str::<u128>("340282366920938463463374607431768211455").is_err());
+    }
+
+    #[test]
+    fn collections_round_trip() {
+        let input = r#"{"a": [1, 2], "b": []}"#;
+        let map: BTreeMap<String, Vec<u16>> = from_str(input).unwrap();
+        assert_eq!(map["a"], [1, 2]);
+        assert_eq!(map.to_json(), parse(input).unwrap());
+
+        let map: HashMap<String, bool> = from_str(r#"{"x": true}"#).unwrap();
+        assert_eq!(map.get("x"), Some(&true));
+
+        let ordered: Map<String, u8> = from_str(r#"{"z": 1, "a": 2}"#).unwrap();
+        assert_eq!(
+            ordered.to_json().to_string(),
+            "{\n    \"z\": 1,\n    \"a\": 2\n}"
+        );
+
+        let tuple: (String, u8, Option<bool>) = from_str(r#"["a", 1, null]"#).unwrap();
+        assert_eq!(tuple, ("a".to_string(), 1, None));
+        assert_eq!(tuple.to_json(), parse(r#"["a", 1, null]"#).unwrap());
+        assert_eq!(
+            from_str::<(u8, u8)>("[1]").unwrap_err().to_string(),
+            "expected an array of length 2, got length 1"
+        );
+    }
+
+    #[test]
+    fn errors_carry_the_path() {
+        let err =
+            from_str::<Vec<HashMap<String, Vec<u8>>>>(r#"[{}, {"a/b": [1, "x"]}]"#).unwrap_err();
+        assert_eq!(err.pointer(), "/1/a~1b/1");
+        assert_eq!(err.to_string(), "expected number, got string at /1/a~1b/1");
+        assert_eq!(
+            err.kind(),
+            &FromJsonErrorKind::InvalidType {
+                expected: JsonKind::Number,
+                found: JsonKind::String
+            }
+        );
+
+        let value = parse(r#"[{}, {"a/b": [1, "x"]}]"#).unwrap();
+        assert_eq!(
+            value.pointer(&err.pointer()).and_then(JsonValue::as_str),
+            Some("x")
+        );
+
+        let err = FromJsonError::missing_field("name").at_index(0);
+        assert_eq!(err.to_string(), "missing field at /0/name");
+    }
+}